@@ -2,16 +2,19 @@ use consul_oxide::{AgentServices, Client, Config, ServiceRegistrationPayload};
 
 #[tokio::main]
 async fn main() {
-    let client = Client::new(Config::default());
+    let client = Client::new(Config::new().unwrap());
     client
-        .register_service(ServiceRegistrationPayload {
-            name: "My Service".to_string(),
-            tags: Some(vec!["tag1".to_string(), "tag2".to_string()]),
-            port: 8080,
-            ..Default::default()
-        })
+        .register_service(
+            ServiceRegistrationPayload {
+                name: "My Service".to_string(),
+                tags: Some(vec!["tag1".to_string(), "tag2".to_string()]),
+                port: 8080,
+                ..Default::default()
+            },
+            None,
+        )
         .await
         .unwrap();
     // print
-    println!("{:?}", client.list_local_services().await.unwrap());
+    println!("{:?}", client.list_local_services(None).await.unwrap());
 }
@@ -173,7 +173,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_check_acl_replication() {
-        let config = Config::default();
+        let config = Config::new().unwrap();
         let client = Client::new(config);
         // this should error on the test instance
         // TODO: devise non-erroring test instance
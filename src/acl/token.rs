@@ -58,7 +58,7 @@ pub struct RoleLink {
 
 /// Request payload for the [AclTokens::update_token] method.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
-struct UpdateToken {
+pub struct UpdateToken {
     /// Free form human readable description of the token.
     pub description: Option<String>,
     /// The list of policies that should be applied to the token.
@@ -79,7 +79,7 @@ struct UpdateToken {
 }
 
 #[async_trait]
-trait AclTokens {
+pub trait AclTokens {
     /// This endpoint creates a new ACL token.
     ///
     /// For more information, see the relevant endpoint's [API documentation].
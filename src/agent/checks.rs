@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use async_trait::async_trait;
 
-use crate::{Client, ConsulResult};
+use crate::{Client, ConsulResult, HealthStatus, QueryOptions};
 
 /// A health check run on a service hosted on this node.
 #[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize, Debug)]
@@ -19,7 +19,7 @@ pub struct AgentCheck {
     pub name: String,
     /// The status of the check.
     #[serde(rename = "Status")]
-    pub status: String,
+    pub status: HealthStatus,
     /// Notes attached to this check.
     #[serde(rename = "Notes")]
     pub notes: String,
@@ -197,7 +197,10 @@ pub trait AgentChecks {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api/agent/check.html#list-checks
-    async fn list_checks(&self) -> ConsulResult<HashMap<String, AgentCheck>>;
+    async fn list_checks(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<HashMap<String, AgentCheck>>;
 
     /// This method registers a check with the local agent.
     ///
@@ -205,7 +208,11 @@ pub trait AgentChecks {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api/agent/check.html#register-check
-    async fn register_check(&self, check: RegisterCheckPayload) -> ConsulResult<()>;
+    async fn register_check(
+        &self,
+        check: RegisterCheckPayload,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()>;
 
     /// This method deregisters a check with the local agent.
     ///
@@ -213,26 +220,41 @@ pub trait AgentChecks {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api/agent/check.html#deregister-check
-    async fn deregister_check(&self, check_id: &str) -> ConsulResult<()>;
+    async fn deregister_check(
+        &self,
+        check_id: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()>;
 }
 
 #[async_trait]
 impl AgentChecks for Client {
     #[tracing::instrument]
-    async fn list_checks(&self) -> ConsulResult<HashMap<String, AgentCheck>> {
-        self.get("/v1/agent/checks", None).await
+    async fn list_checks(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<HashMap<String, AgentCheck>> {
+        self.get("/v1/agent/checks", options).await
     }
 
     #[tracing::instrument]
-    async fn register_check(&self, check: RegisterCheckPayload) -> ConsulResult<()> {
-        self.put_with_empty("/v1/agent/check/register", check, None, None)
+    async fn register_check(
+        &self,
+        check: RegisterCheckPayload,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()> {
+        self.put_with_empty("/v1/agent/check/register", check, None, options)
             .await
             .map(|_: Option<()>| ())
     }
 
     #[tracing::instrument]
-    async fn deregister_check(&self, check_id: &str) -> ConsulResult<()> {
-        self.put_with_empty(&format!("/v1/agent/check/deregister/{}", check_id), (), None, None)
+    async fn deregister_check(
+        &self,
+        check_id: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()> {
+        self.put_with_empty(&format!("/v1/agent/check/deregister/{}", check_id), (), None, options)
             .await
             .map(|_: Option<()>| ())
     }
@@ -244,8 +266,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_list_checks() {
-        let client = Client::new(Config::default());
-        let result = client.list_checks().await.unwrap();
+        let client = Client::new(Config::new().unwrap());
+        let result = client.list_checks(None).await.unwrap();
         assert_eq!(result.len(), 0);
         println!("{:?}", result);
     }
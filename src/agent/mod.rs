@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use async_trait::async_trait;
 
-use crate::{sealed::Sealed, Client, ConsulResult};
+use crate::{sealed::Sealed, Client, ConsulResult, QueryOptions, ServiceWeights};
 
 mod checks;
 mod service;
@@ -52,7 +52,7 @@ pub struct AgentMember {
 /// A service hosted on this node.
 ///
 /// For more information, see [Agent::list_members].
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default)]
 pub struct AgentService {
     /// The ID of the service in the agent.
@@ -73,6 +73,10 @@ pub struct AgentService {
     /// Whether tags are being overridden.
     #[serde(rename = "EnableTagOverride")]
     pub enable_tag_override: bool,
+    /// The weights used by DNS and load balancers to distribute traffic to
+    /// this service.
+    #[serde(rename = "Weights")]
+    pub weights: Option<ServiceWeights>,
     #[serde(rename = "CreateIndex")]
     pub create_index: u64,
     #[serde(rename = "ModifyIndex")]
@@ -101,7 +105,11 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent#list-members.
-    async fn list_members(&self, wan: bool) -> ConsulResult<AgentMember>;
+    async fn list_members(
+        &self,
+        wan: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<AgentMember>;
 
     /// This method instructs the agent to reload its configuration.
     ///
@@ -109,7 +117,7 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent#reload-agent.
-    async fn reload_agent(&self) -> ConsulResult<()>;
+    async fn reload_agent(&self, options: Option<QueryOptions>) -> ConsulResult<()>;
 
     /// This method places the agent into "maintenance mode". During maintenance
     /// mode, the node will be marked as unavailable and will not be present in
@@ -119,8 +127,12 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent#enable-maintenance-mode.
-    async fn enable_maintenance_mode(&self, enable: bool, reason: Option<&str>)
-        -> ConsulResult<()>;
+    async fn enable_maintenance_mode(
+        &self,
+        enable: bool,
+        reason: Option<&str>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()>;
 
     /// This method instructs the agent to attempt to connect to a given
     /// address.
@@ -129,7 +141,12 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent#join-agent.
-    async fn join_cluster(&self, address: &str, wan: bool) -> ConsulResult<()>;
+    async fn join_cluster(
+        &self,
+        address: &str,
+        wan: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()>;
 
     /// This endpoint triggers a graceful leave and shutdown of the agent. It is
     /// used to ensure other nodes see the agent as "left" instead of "failed".
@@ -138,7 +155,7 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api/agent.html#graceful-leave-and-shutdown.
-    async fn leave_cluster(&self) -> ConsulResult<()>;
+    async fn leave_cluster(&self, options: Option<QueryOptions>) -> ConsulResult<()>;
 
     /// This endpoint instructs the agent to force a node into the left state in
     /// the LAN and WAN gossip pools. If a node fails unexpectedly, then it will
@@ -148,23 +165,27 @@ pub trait Agent: Sealed {
     /// documentation].
     ///
     /// [API documentation]:  https://www.consul.io/api-docs/agent#force-leave-and-shutdown.
-    async fn force_leave_cluster(&self) -> ConsulResult<()>;
+    async fn force_leave_cluster(&self, options: Option<QueryOptions>) -> ConsulResult<()>;
 }
 
 #[async_trait]
 impl Agent for Client {
     #[tracing::instrument]
-    async fn list_members(&self, wan: bool) -> ConsulResult<AgentMember> {
-        let mut params = HashMap::new();
+    async fn list_members(
+        &self,
+        wan: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<AgentMember> {
+        let mut params = Vec::new();
         if wan {
-            params.insert(String::from("wan"), String::from("1"));
+            params.push((String::from("wan"), String::from("1")));
         }
-        self.get("/v1/agent/members", None).await
+        self.get_with_params("/v1/agent/members", Some(params), options).await
     }
 
     #[tracing::instrument]
-    async fn reload_agent(&self) -> ConsulResult<()> {
-        self.put("/v1/agent/reload", (), None, None).await
+    async fn reload_agent(&self, options: Option<QueryOptions>) -> ConsulResult<()> {
+        self.put("/v1/agent/reload", (), None, options).await
     }
 
     #[tracing::instrument]
@@ -172,34 +193,40 @@ impl Agent for Client {
         &self,
         enable: bool,
         reason: Option<&str>,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<()> {
-        let mut params = HashMap::new();
+        let mut params = Vec::new();
         let enable_str = if enable { String::from("true") } else { String::from("false") };
-        params.insert(String::from("enabled"), enable_str);
+        params.push((String::from("enabled"), enable_str));
         if let Some(r) = reason {
-            params.insert(String::from("reason"), r.to_owned());
+            params.push((String::from("reason"), r.to_owned()));
         }
-        self.put("/v1/agent/maintenance", (), Some(params), None).await
+        self.put("/v1/agent/maintenance", (), Some(params), options).await
     }
 
     #[tracing::instrument]
-    async fn join_cluster(&self, address: &str, wan: bool) -> ConsulResult<()> {
-        let mut params = HashMap::new();
+    async fn join_cluster(
+        &self,
+        address: &str,
+        wan: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()> {
+        let mut params = Vec::new();
 
         if wan {
-            params.insert(String::from("wan"), String::from("true"));
+            params.push((String::from("wan"), String::from("true")));
         }
         let path = format!("/v1/agent/join/{}", address);
-        self.put(&path, (), Some(params), None).await
+        self.put(&path, (), Some(params), options).await
     }
 
     #[tracing::instrument]
-    async fn leave_cluster(&self) -> ConsulResult<()> {
-        self.put("/v1/agent/leave", (), None, None).await
+    async fn leave_cluster(&self, options: Option<QueryOptions>) -> ConsulResult<()> {
+        self.put("/v1/agent/leave", (), None, options).await
     }
 
     #[tracing::instrument]
-    async fn force_leave_cluster(&self) -> ConsulResult<()> {
-        self.put("/v1/agent/force-leave", (), None, None).await
+    async fn force_leave_cluster(&self, options: Option<QueryOptions>) -> ConsulResult<()> {
+        self.put("/v1/agent/force-leave", (), None, options).await
     }
 }
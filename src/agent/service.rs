@@ -2,7 +2,9 @@ use std::{collections::HashMap, fmt::Debug};
 
 use async_trait::async_trait;
 
-use crate::{sealed::Sealed, Client, ConsulResult, HealthCheck, ServiceWeights, TaggedAddress};
+use crate::{
+    sealed::Sealed, Client, ConsulResult, HealthCheck, QueryOptions, ServiceWeights, TaggedAddress,
+};
 
 /// A service registered with the local agent.
 ///
@@ -121,7 +123,10 @@ pub trait AgentServices: Sealed {
     /// For more information, see the relevant endpoint's [API documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent/service#list-services
-    async fn list_local_services(&self) -> ConsulResult<Vec<Service>>;
+    async fn list_local_services(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<Service>>;
 
     /// This method returns the full service definition for a single service
     /// instance registered on the local agent.
@@ -132,6 +137,7 @@ pub trait AgentServices: Sealed {
     async fn get_local_service_config<S: AsRef<str> + Send + Debug>(
         &self,
         id: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<ServiceConfig>;
 
     /// This method retrieves an aggregated state of service(s) on the local
@@ -143,6 +149,7 @@ pub trait AgentServices: Sealed {
     async fn get_local_service_health<S: AsRef<str> + Send + Debug>(
         &self,
         name: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<HealthCheck>;
 
     /// This method retrieves the health state of a specific service on the
@@ -150,6 +157,7 @@ pub trait AgentServices: Sealed {
     async fn get_local_service_health_by_id<S: AsRef<str> + Send + Debug>(
         &self,
         id: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<HealthCheck>;
 
     /// This endpoint adds a new service, with optional health checks, to the
@@ -158,14 +166,22 @@ pub trait AgentServices: Sealed {
     /// For more information, see the relevant endpoint's [API documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/agent/service#register-service
-    async fn register_service(&self, payload: ServiceRegistrationPayload) -> ConsulResult<()>;
+    async fn register_service(
+        &self,
+        payload: ServiceRegistrationPayload,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()>;
 }
 
 #[async_trait]
 impl AgentServices for Client {
     #[tracing::instrument]
-    async fn list_local_services(&self) -> ConsulResult<Vec<Service>> {
-        let services: HashMap<String, Service> = match self.get("/v1/agent/services", None).await {
+    async fn list_local_services(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<Service>> {
+        let services: HashMap<String, Service> = match self.get("/v1/agent/services", options).await
+        {
             Ok(services) => services,
             Err(e) => return Err(e),
         };
@@ -176,29 +192,36 @@ impl AgentServices for Client {
     async fn get_local_service_config<S: AsRef<str> + Send + Debug>(
         &self,
         name: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<ServiceConfig> {
-        self.get(format!("/v1/agent/services/{}", name.as_ref()), None).await
+        self.get(format!("/v1/agent/services/{}", name.as_ref()), options).await
     }
 
     #[tracing::instrument]
     async fn get_local_service_health<S: AsRef<str> + Send + Debug>(
         &self,
         name: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<HealthCheck> {
-        self.get(format!("/v1/agent/health/service/{}", name.as_ref()), None).await
+        self.get(format!("/v1/agent/health/service/{}", name.as_ref()), options).await
     }
 
     #[tracing::instrument]
     async fn get_local_service_health_by_id<S: AsRef<str> + Send + Debug>(
         &self,
         id: S,
+        options: Option<QueryOptions>,
     ) -> ConsulResult<HealthCheck> {
-        self.get(format!("/v1/agent/health/service/id/{}", id.as_ref()), None).await
+        self.get(format!("/v1/agent/health/service/id/{}", id.as_ref()), options).await
     }
 
     #[tracing::instrument]
-    async fn register_service(&self, payload: ServiceRegistrationPayload) -> ConsulResult<()> {
-        self.put_with_empty("/v1/agent/service/register", payload, None, None)
+    async fn register_service(
+        &self,
+        payload: ServiceRegistrationPayload,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<()> {
+        self.put_with_empty("/v1/agent/service/register", payload, None, options)
             .await
             .map(|_: Option<()>| ())
     }
@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use futures::StreamExt;
+use rand::Rng;
+use tokio::task::JoinHandle;
+
+use crate::{
+    watch::{Watch, WatchOptions},
+    Client, ConsulError, ConsulResult, HealthStatus, ServiceEntry,
+};
+
+/// The strategy used by a [LoadBalancer] to pick the next instance.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BalancingStrategy {
+    /// Instances are picked in turn.
+    #[default]
+    RoundRobin,
+    /// Instances are picked at random.
+    Random,
+    /// Instances are picked at random, in proportion to their
+    /// [ServiceWeights](crate::ServiceWeights). The passing weight is used for
+    /// passing instances, and the warning weight for warning instances.
+    /// Instances without weights have a weight of 1, and instances with a
+    /// weight of 0 are never picked.
+    Weighted,
+    /// The instance which was picked the longest time ago, or never, is picked.
+    LeastRecentlyUsed,
+}
+
+/// The address handed out by a [LoadBalancer] for an instance.
+///
+/// Tagged addresses are read from [Node::taggedaddresses](crate::Node), and
+/// fall back to the default address if the node has no such address.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AddressKind {
+    /// The address of the service, or of its node if the service has none.
+    #[default]
+    Default,
+    /// The `lan` tagged address of the node.
+    Lan,
+    /// The `wan` tagged address of the node.
+    Wan,
+    /// The `lan_ipv6` tagged address of the node.
+    LanIpv6,
+    /// The `wan_ipv6` tagged address of the node.
+    WanIpv6,
+}
+
+impl AddressKind {
+    /// This method returns the key of the tagged address, if any.
+    fn tag(self) -> Option<&'static str> {
+        match self {
+            AddressKind::Default => None,
+            AddressKind::Lan => Some("lan"),
+            AddressKind::Wan => Some("wan"),
+            AddressKind::LanIpv6 => Some("lan_ipv6"),
+            AddressKind::WanIpv6 => Some("wan_ipv6"),
+        }
+    }
+}
+
+/// Options used to start a [LoadBalancer].
+#[derive(Clone, Debug, Default)]
+pub struct BalancerOptions {
+    /// The name of the service to balance over.
+    pub service: String,
+    /// If not empty, only instances with all of these tags are used.
+    pub tags: Vec<String>,
+    /// The strategy used to pick the next instance.
+    pub strategy: BalancingStrategy,
+    /// The address handed out for each instance.
+    pub address: AddressKind,
+    /// If set, instances with warning checks are used alongside passing ones.
+    pub include_warning: bool,
+    /// The options used to watch the instances of the service.
+    pub watch_options: WatchOptions,
+}
+
+/// The address of a service instance, as handed out by a [LoadBalancer].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ServiceAddress {
+    /// The host name or IP address of the instance.
+    pub host: String,
+    /// The port of the instance.
+    pub port: u16,
+}
+
+impl fmt::Display for ServiceAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// A client-side load balancer over the healthy instances of a service.
+///
+/// The instances are watched using blocking queries, so the balancer always
+/// picks from the latest known set of healthy instances. If the watch fails,
+/// the last known set is kept until the watch recovers.
+///
+/// The balancer runs in a background task until it is dropped, so it must be
+/// started from within a Tokio runtime.
+///
+/// ```
+/// use consul_oxide::{BalancerOptions, BalancingStrategy, Client, Config, LoadBalancer};
+///
+/// let client = Client::new(Config::new().unwrap());
+/// let options = BalancerOptions {
+///     service: String::from("web"),
+///     strategy: BalancingStrategy::LeastRecentlyUsed,
+///     ..Default::default()
+/// };
+/// let address = async {
+///     let balancer = LoadBalancer::start(&client, options).await?;
+///     Ok::<_, consul_oxide::ConsulError>(balancer.next_address())
+/// };
+/// ```
+#[derive(Debug)]
+pub struct LoadBalancer {
+    instances: Arc<Mutex<Instances>>,
+    strategy: BalancingStrategy,
+    address: AddressKind,
+    task: JoinHandle<()>,
+}
+
+impl LoadBalancer {
+    /// This method starts balancing over the service described by the given
+    /// options. It returns once the first set of instances has been loaded.
+    pub async fn start(client: &Client, options: BalancerOptions) -> ConsulResult<LoadBalancer> {
+        if options.service.is_empty() {
+            return Err(ConsulError::MissingParameter(String::from("service")));
+        }
+        let tags: Vec<&str> = options.tags.iter().map(String::as_str).collect();
+        let include_warning = options.include_warning;
+        let mut stream = client.watch_service_instances(
+            &options.service,
+            &tags,
+            !include_warning,
+            Some(options.watch_options.clone()),
+        );
+        let mut instances = Instances::default();
+        if let Some(result) = stream.next().await {
+            instances.update(result?, include_warning);
+        }
+        let instances = Arc::new(Mutex::new(instances));
+        let task = {
+            let instances = instances.clone();
+            let service = options.service.clone();
+            tokio::spawn(async move {
+                while let Some(result) = stream.next().await {
+                    match result {
+                        Ok(entries) => lock(&instances).update(entries, include_warning),
+                        Err(err) => {
+                            tracing::warn!("failed to watch instances of {}: {}", service, err)
+                        }
+                    }
+                }
+            })
+        };
+        Ok(LoadBalancer { instances, strategy: options.strategy, address: options.address, task })
+    }
+
+    /// This method picks the next instance, or returns `None` if there are no
+    /// healthy instances.
+    pub fn next_instance(&self) -> Option<ServiceEntry> {
+        lock(&self.instances).pick(self.strategy).cloned()
+    }
+
+    /// This method picks the next instance and returns its address, or returns
+    /// `None` if there are no healthy instances.
+    pub fn next_address(&self) -> Option<ServiceAddress> {
+        lock(&self.instances).pick(self.strategy).map(|entry| address_of(entry, self.address))
+    }
+
+    /// This method returns the current set of healthy instances.
+    pub fn instances(&self) -> Vec<ServiceEntry> {
+        lock(&self.instances).entries.clone()
+    }
+}
+
+impl Drop for LoadBalancer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// This function locks the instances, ignoring poisoning since they are
+/// always left in a consistent state.
+fn lock(instances: &Mutex<Instances>) -> MutexGuard<'_, Instances> {
+    instances.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The set of healthy instances, and the state of the balancing strategies.
+#[derive(Debug, Default)]
+struct Instances {
+    entries: Vec<ServiceEntry>,
+    /// The position of the next instance picked by round-robin.
+    next: usize,
+    /// The number of instances picked so far, used as a logical clock.
+    picks: u64,
+    /// The logical time each instance was last picked at, by node and
+    /// service ID.
+    last_picked: HashMap<(String, String), u64>,
+}
+
+impl Instances {
+    /// This method replaces the instances with the healthy ones among the
+    /// given entries.
+    fn update(&mut self, entries: Vec<ServiceEntry>, include_warning: bool) {
+        self.entries = entries
+            .into_iter()
+            .filter(|entry| match entry.aggregated_status() {
+                HealthStatus::Passing => true,
+                HealthStatus::Warning => include_warning,
+                _ => false,
+            })
+            .collect();
+        let keys: Vec<_> = self.entries.iter().map(key).collect();
+        self.last_picked.retain(|k, _| keys.contains(k));
+    }
+
+    /// This method picks the next instance using the given strategy.
+    fn pick(&mut self, strategy: BalancingStrategy) -> Option<&ServiceEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let index = match strategy {
+            BalancingStrategy::RoundRobin => {
+                let index = self.next % self.entries.len();
+                self.next = index + 1;
+                index
+            }
+            BalancingStrategy::Random => rand::thread_rng().gen_range(0..self.entries.len()),
+            BalancingStrategy::Weighted => {
+                let weights: Vec<u64> = self.entries.iter().map(weight).collect();
+                let total: u64 = weights.iter().sum();
+                if total == 0 {
+                    return None;
+                }
+                let mut target = rand::thread_rng().gen_range(0..total);
+                weights
+                    .iter()
+                    .position(|weight| {
+                        if target < *weight {
+                            return true;
+                        }
+                        target -= weight;
+                        false
+                    })
+                    .unwrap_or_default()
+            }
+            BalancingStrategy::LeastRecentlyUsed => (0..self.entries.len())
+                .min_by_key(|i| {
+                    self.last_picked.get(&key(&self.entries[*i])).copied().unwrap_or_default()
+                })
+                .unwrap_or_default(),
+        };
+        self.picks += 1;
+        self.last_picked.insert(key(&self.entries[index]), self.picks);
+        Some(&self.entries[index])
+    }
+}
+
+/// This function returns the key identifying an instance.
+fn key(entry: &ServiceEntry) -> (String, String) {
+    (entry.node.node.clone(), entry.service.id.clone())
+}
+
+/// This function returns the weight of an instance for the weighted strategy.
+fn weight(entry: &ServiceEntry) -> u64 {
+    match &entry.service.weights {
+        Some(weights) if entry.aggregated_status() == HealthStatus::Warning => {
+            weights.warning.into()
+        }
+        Some(weights) => weights.passing.into(),
+        None => 1,
+    }
+}
+
+/// This function returns the address of an instance.
+fn address_of(entry: &ServiceEntry, kind: AddressKind) -> ServiceAddress {
+    let tagged = kind
+        .tag()
+        .and_then(|tag| entry.node.taggedaddresses.as_ref()?.get(tag))
+        .filter(|address| !address.is_empty());
+    let host = match tagged {
+        Some(address) => address.clone(),
+        None if !entry.service.address.is_empty() => entry.service.address.clone(),
+        None => entry.node.address.clone(),
+    };
+    ServiceAddress { host, port: entry.service.port }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{address_of, AddressKind, BalancingStrategy, Instances, ServiceAddress};
+    use crate::{AgentService, HealthCheck, HealthStatus, Node, ServiceEntry, ServiceWeights};
+
+    fn entry(node: &str, status: HealthStatus, weights: Option<(u32, u32)>) -> ServiceEntry {
+        ServiceEntry {
+            node: Node {
+                node: node.to_owned(),
+                address: format!("{}.node", node),
+                ..Default::default()
+            },
+            service: AgentService {
+                id: String::from("web"),
+                service: String::from("web"),
+                port: 8080,
+                weights: weights.map(|(passing, warning)| ServiceWeights { passing, warning }),
+                ..Default::default()
+            },
+            checks: vec![HealthCheck { status, ..Default::default() }],
+        }
+    }
+
+    fn instances(entries: Vec<ServiceEntry>, include_warning: bool) -> Instances {
+        let mut instances = Instances::default();
+        instances.update(entries, include_warning);
+        instances
+    }
+
+    fn pick(instances: &mut Instances, strategy: BalancingStrategy) -> String {
+        instances.pick(strategy).unwrap().node.node.clone()
+    }
+
+    #[test]
+    fn test_update_filters_unhealthy() {
+        let entries = vec![
+            entry("a", HealthStatus::Passing, None),
+            entry("b", HealthStatus::Warning, None),
+            entry("c", HealthStatus::Critical, None),
+        ];
+        assert_eq!(instances(entries.clone(), false).entries.len(), 1);
+        assert_eq!(instances(entries, true).entries.len(), 2);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut instances = instances(
+            vec![entry("a", HealthStatus::Passing, None), entry("b", HealthStatus::Passing, None)],
+            false,
+        );
+        let picked: Vec<String> =
+            (0..3).map(|_| pick(&mut instances, BalancingStrategy::RoundRobin)).collect();
+        assert_eq!(picked, ["a", "b", "a"]);
+        assert!(Instances::default().pick(BalancingStrategy::RoundRobin).is_none());
+    }
+
+    #[test]
+    fn test_weighted() {
+        let mut instances = instances(
+            vec![
+                entry("a", HealthStatus::Passing, Some((0, 1))),
+                entry("b", HealthStatus::Warning, Some((1, 0))),
+                entry("c", HealthStatus::Passing, Some((3, 1))),
+            ],
+            true,
+        );
+        for _ in 0..16 {
+            assert_eq!(pick(&mut instances, BalancingStrategy::Weighted), "c");
+        }
+        instances.entries.pop();
+        assert!(instances.pick(BalancingStrategy::Weighted).is_none());
+    }
+
+    #[test]
+    fn test_least_recently_used() {
+        let entries = vec![
+            entry("a", HealthStatus::Passing, None),
+            entry("b", HealthStatus::Passing, None),
+            entry("c", HealthStatus::Passing, None),
+        ];
+        let mut instances = instances(entries.clone(), false);
+        assert_eq!(pick(&mut instances, BalancingStrategy::LeastRecentlyUsed), "a");
+        assert_eq!(pick(&mut instances, BalancingStrategy::RoundRobin), "a");
+        assert_eq!(pick(&mut instances, BalancingStrategy::LeastRecentlyUsed), "b");
+        // new instances have never been picked
+        instances.update(
+            vec![entries[0].clone(), entries[1].clone(), entry("d", HealthStatus::Passing, None)],
+            false,
+        );
+        assert_eq!(pick(&mut instances, BalancingStrategy::LeastRecentlyUsed), "d");
+        assert_eq!(pick(&mut instances, BalancingStrategy::LeastRecentlyUsed), "a");
+    }
+
+    #[test]
+    fn test_address_of() {
+        let mut entry = entry("a", HealthStatus::Passing, None);
+        entry.node.taggedaddresses = Some(HashMap::from([
+            (String::from("lan"), String::from("10.0.0.1")),
+            (String::from("wan_ipv6"), String::from("2001:db8::1")),
+        ]));
+        let address = |entry: &ServiceEntry, kind| address_of(entry, kind).to_string();
+        assert_eq!(address(&entry, AddressKind::Default), "a.node:8080");
+        assert_eq!(address(&entry, AddressKind::Lan), "10.0.0.1:8080");
+        assert_eq!(address(&entry, AddressKind::Wan), "a.node:8080");
+        assert_eq!(address(&entry, AddressKind::WanIpv6), "[2001:db8::1]:8080");
+        entry.service.address = String::from("10.0.0.2");
+        assert_eq!(
+            address_of(&entry, AddressKind::Wan),
+            ServiceAddress { host: String::from("10.0.0.2"), port: 8080 }
+        );
+    }
+}
@@ -3,126 +3,162 @@ use std::collections::HashMap;
 use async_trait::async_trait;
 
 use crate::{
-    agent::AgentService, sealed::Sealed, AgentCheck, Client, ConsulResult, QueryOptions,
-    ServiceWeights,
+    agent::AgentService, sealed::Sealed, AgentCheck, Client, ConsulResult, Node, QueryMeta,
+    QueryOptions, ServiceWeights,
 };
 
-/// A node within the cluster gossip pool.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
-#[serde(default, rename_all = "PascalCase")]
-pub struct Node {
-    /// The ID of the node.
-    #[serde(rename = "ID")]
-    id: String,
-    /// The name of the node.
-    node: String,
-    /// The address of the node.
-    address: String,
-    /// The datacenter of the node.
-    datacenter: String,
-    /// The tags of the node.
-    tagged_addresses: HashMap<String, String>,
-    /// The meta data of the node.
-    meta: HashMap<String, String>,
-    create_index: u64,
-    modify_index: u64,
-}
-
 /// A service defined within the Agent catalog.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct CatalogService {
     /// The ID of the service.
     #[serde(rename = "ID")]
-    id: String,
+    pub id: String,
     /// The node the service is associated with.
-    node: String,
+    pub node: String,
     /// The address of the node.
-    address: String,
+    pub address: String,
     /// The datacenter of the node running the service.
-    datacenter: String,
+    pub datacenter: String,
     /// A map of addresses tagged to the node hosting the service.
-    tagged_addresses: HashMap<String, String>,
+    pub tagged_addresses: HashMap<String, String>,
     /// Metadata attached to the node this service is hosted on.
-    node_meta: HashMap<String, String>,
+    pub node_meta: HashMap<String, String>,
     /// The ID of the service.
     #[serde(rename = "ServiceID")]
-    service_id: String,
+    pub service_id: String,
     /// The name of the service.
-    service_name: String,
+    pub service_name: String,
     /// The address of the service.
-    service_address: String,
+    pub service_address: String,
     /// Tags assigned to the service.
-    service_tags: Vec<String>,
+    pub service_tags: Vec<String>,
     /// Metadata assigned to the service.
-    service_meta: HashMap<String, String>,
+    pub service_meta: HashMap<String, String>,
     /// The port of the service.
-    service_port: u32,
-    service_weights: ServiceWeights,
-    service_enable_tag_override: bool,
-    create_index: u64,
-    modify_index: u64,
+    pub service_port: u32,
+    /// The weights of the service.
+    pub service_weights: ServiceWeights,
+    /// Whether anti-entropy for the service's tags is disabled.
+    pub service_enable_tag_override: bool,
+    pub create_index: u64,
+    pub modify_index: u64,
 }
 
-/// A response datatype containing a [Node] and its services.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+/// A response datatype containing a [Node] and its services, keyed by ID.
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct CatalogNode {
     /// The node stored in the catalog.
-    node: Option<Node>,
+    pub node: Option<Node>,
+    /// The services associated with the node.
+    pub services: HashMap<String, AgentService>,
+}
+
+/// A response datatype containing a [Node] and a list of its services.
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct CatalogNodeServices {
+    /// The node stored in the catalog.
+    pub node: Option<Node>,
     /// The services associated with the node.
-    services: HashMap<String, AgentService>,
+    pub services: Vec<AgentService>,
+}
+
+/// The name of a service, qualified by its namespace in Consul Enterprise.
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct CompoundServiceName {
+    /// The name of the service.
+    pub name: String,
+    /// The namespace of the service.
+    #[cfg(feature = "enterprise")]
+    pub namespace: String,
+}
+
+/// A service associated with a gateway, returned by
+/// [Catalog::list_gateway_services].
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct GatewayService {
+    /// The gateway the service is associated with.
+    pub gateway: CompoundServiceName,
+    /// The service associated with the gateway. For wildcard entries of
+    /// ingress gateways, this is `*`.
+    pub service: CompoundServiceName,
+    /// The kind of the gateway, either `ingress-gateway` or
+    /// `terminating-gateway`.
+    pub gateway_kind: String,
+    /// The port the service is exposed on by an ingress gateway.
+    pub port: u16,
+    /// The protocol of the service exposed by an ingress gateway.
+    pub protocol: String,
+    /// The hosts the service is exposed on by an ingress gateway.
+    pub hosts: Option<Vec<String>>,
+    /// Whether the service was associated through a wildcard entry.
+    pub from_wildcard: bool,
+    /// The CA file used by a terminating gateway to connect to the service.
+    #[serde(rename = "CAFile")]
+    pub ca_file: String,
+    /// The certificate file used by a terminating gateway to connect to the
+    /// service.
+    pub cert_file: String,
+    /// The key file used by a terminating gateway to connect to the service.
+    pub key_file: String,
+    /// The SNI used by a terminating gateway to connect to the service.
+    #[serde(rename = "SNI")]
+    pub sni: String,
 }
 
 /// Datatype containing payload data for the [crate::Catalog::register] method.
 ///
 /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#json-request-body-schema).
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct CatalogRegistrationPayload {
     /// An optional UUID to assign to the node. This must be a 36-character
     /// UUID-formatted string.
-    #[serde(rename = "Node")]
-    id: String,
+    #[serde(rename = "ID")]
+    pub id: String,
     /// Specifies the node ID to register.
-    node: String,
+    pub node: String,
     /// Specifies the address to register.
-    address: String,
+    pub address: String,
     /// Specifies the tagged addresses.
-    tagged_addresses: HashMap<String, String>,
+    pub tagged_addresses: HashMap<String, String>,
     /// Specifies arbitrary KV metadata pairs for filtering purposes.
-    node_meta: HashMap<String, String>,
+    pub node_meta: HashMap<String, String>,
     /// Specifies the datacenter, which defaults to the agent's datacenter if
     /// not provided.
-    datacenter: String,
+    pub datacenter: String,
     /// Specifies to register a service. If `id` is not provided, it will be
     /// defaulted to the value of the Service.Service property. Only one service
     /// with a given ID may be present per node.
-    service: Option<AgentService>,
+    pub service: Option<AgentService>,
     /// Specifies to register a check.
-    check: Option<AgentCheck>,
+    pub check: Option<AgentCheck>,
     /// Specifies whether to skip updating the node's information in the
     /// registration.
-    skip_node_update: bool,
+    pub skip_node_update: bool,
 }
 
 /// Request payload datatype for the [crate::Catalog::deregister] method.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct CatalogDeregistrationPayload {
     /// Specifies the node ID to deregister.
-    node: String,
+    pub node: String,
     /// The address of the node.
-    address: String,
+    pub address: String,
     /// Specifies the datacenter, which defaults to the agent's datacenter if
     /// not provided.
-    datacenter: String,
+    pub datacenter: String,
     /// Specifies the service ID to deregister.
     #[serde(rename = "ServiceID")]
-    service_id: String,
+    pub service_id: String,
     /// Specifies the check ID to deregister.
     #[serde(rename = "CheckID")]
-    check_id: String,
+    pub check_id: String,
 }
 
 /// This trait provides methods for interacting with the Agent catalogue.
@@ -157,15 +193,21 @@ pub trait Catalog: Sealed {
     /// trip time from the server to the servers in that datacenter.
     ///
     /// For more information, see the [API documentation](https://www.consul.io/api/catalog.html#list-datacenters).
-    async fn list_datacenters(&self) -> ConsulResult<Vec<String>>;
+    async fn list_datacenters(&self, q: Option<QueryOptions>) -> ConsulResult<Vec<String>>;
 
     /// This endpoint and returns the nodes registered in a given datacenter.
     ///
     /// For more information, see the [API documentation](https://www.consul.io/api/catalog.html#list-nodes).
-    async fn list_datacenter_nodes(
+    async fn list_datacenter_nodes(&self, q: Option<QueryOptions>) -> ConsulResult<Vec<Node>>;
+
+    /// This endpoint returns the nodes registered in a given datacenter, along
+    /// with the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_datacenter_nodes] for more information.
+    async fn list_datacenter_nodes_with_meta(
         &self,
         q: Option<QueryOptions>,
-    ) -> ConsulResult<HashMap<String, Vec<Node>>>;
+    ) -> ConsulResult<(Vec<Node>, QueryMeta)>;
 
     /// This endpoint returns the services registered in a given datacenter.
     ///
@@ -174,6 +216,120 @@ pub trait Catalog: Sealed {
         &self,
         q: Option<QueryOptions>,
     ) -> ConsulResult<HashMap<String, Vec<String>>>;
+
+    /// This endpoint returns the services registered in a given datacenter,
+    /// along with the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_datacenter_services] for more information.
+    async fn list_datacenter_services_with_meta(
+        &self,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(HashMap<String, Vec<String>>, QueryMeta)>;
+
+    /// This method returns the nodes providing the given service. Only
+    /// instances with all of the given tags are returned.
+    ///
+    /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#list-nodes-for-service).
+    async fn list_service_nodes(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<CatalogService>>;
+
+    /// This method returns the nodes providing the given service, along with
+    /// the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_service_nodes] for more information.
+    async fn list_service_nodes_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<CatalogService>, QueryMeta)>;
+
+    /// This method returns the nodes providing the given service through
+    /// Connect, either as a proxy for the service or natively. Only instances
+    /// with all of the given tags are returned.
+    ///
+    /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#list-nodes-for-connect-capable-service).
+    async fn list_connect_nodes(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<CatalogService>>;
+
+    /// This method returns the nodes providing the given service through
+    /// Connect, along with the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_connect_nodes] for more information.
+    async fn list_connect_nodes_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<CatalogService>, QueryMeta)>;
+
+    /// This method returns the given node and its services, keyed by ID, or
+    /// `None` if the node does not exist.
+    ///
+    /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#retrieve-map-of-services-for-a-node).
+    async fn get_node(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Option<CatalogNode>>;
+
+    /// This method returns the given node and its services, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [Catalog::get_node] for more information.
+    async fn get_node_with_meta(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<CatalogNode>, QueryMeta)>;
+
+    /// This method returns the given node and a list of its services, or `None`
+    /// if the node does not exist.
+    ///
+    /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#list-services-for-node).
+    async fn list_node_services(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Option<CatalogNodeServices>>;
+
+    /// This method returns the given node and a list of its services, along
+    /// with the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_node_services] for more information.
+    async fn list_node_services_with_meta(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<CatalogNodeServices>, QueryMeta)>;
+
+    /// This method returns the services associated with the given ingress or
+    /// terminating gateway.
+    ///
+    /// For more information, see the [API documentation](https://www.consul.io/api-docs/catalog#list-services-for-gateway).
+    async fn list_gateway_services(
+        &self,
+        gateway: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<GatewayService>>;
+
+    /// This method returns the services associated with the given gateway,
+    /// along with the [QueryMeta] of the response.
+    ///
+    /// See [Catalog::list_gateway_services] for more information.
+    async fn list_gateway_services_with_meta(
+        &self,
+        gateway: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<GatewayService>, QueryMeta)>;
 }
 
 #[async_trait]
@@ -184,7 +340,7 @@ impl Catalog for Client {
         payload: CatalogRegistrationPayload,
         options: Option<QueryOptions>,
     ) -> ConsulResult<()> {
-        self.put("/v1/session/create", payload, None, options).await
+        self.put("/v1/catalog/register", payload, None, options).await.map(|_: bool| ())
     }
 
     #[tracing::instrument]
@@ -193,20 +349,25 @@ impl Catalog for Client {
         payload: CatalogDeregistrationPayload,
         options: Option<QueryOptions>,
     ) -> ConsulResult<()> {
-        self.put("/v1/catalog/deregister", payload, None, options).await
+        self.put("/v1/catalog/deregister", payload, None, options).await.map(|_: bool| ())
     }
 
     #[tracing::instrument]
-    async fn list_datacenters(&self) -> ConsulResult<Vec<String>> {
-        self.get("/v1/catalog/datacenters", None).await
+    async fn list_datacenters(&self, q: Option<QueryOptions>) -> ConsulResult<Vec<String>> {
+        self.get("/v1/catalog/datacenters", q).await
     }
 
     #[tracing::instrument]
-    async fn list_datacenter_nodes(
+    async fn list_datacenter_nodes(&self, q: Option<QueryOptions>) -> ConsulResult<Vec<Node>> {
+        self.list_datacenter_nodes_with_meta(q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_datacenter_nodes_with_meta(
         &self,
         q: Option<QueryOptions>,
-    ) -> ConsulResult<HashMap<String, Vec<Node>>> {
-        self.get("/v1/catalog/nodes", q).await
+    ) -> ConsulResult<(Vec<Node>, QueryMeta)> {
+        self.get_with_meta("/v1/catalog/nodes", None, q).await
     }
 
     #[tracing::instrument]
@@ -214,25 +375,175 @@ impl Catalog for Client {
         &self,
         options: Option<QueryOptions>,
     ) -> ConsulResult<HashMap<String, Vec<String>>> {
-        self.get("/v1/catalog/services", options).await
+        self.list_datacenter_services_with_meta(options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_datacenter_services_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(HashMap<String, Vec<String>>, QueryMeta)> {
+        self.get_with_meta("/v1/catalog/services", None, options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_service_nodes(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<CatalogService>> {
+        self.list_service_nodes_with_meta(service, tags, q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_service_nodes_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<CatalogService>, QueryMeta)> {
+        let path = format!("/v1/catalog/service/{}", service);
+        self.get_with_meta(&path, Some(tag_params(tags)), q).await
+    }
+
+    #[tracing::instrument]
+    async fn list_connect_nodes(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<CatalogService>> {
+        self.list_connect_nodes_with_meta(service, tags, q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_connect_nodes_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<CatalogService>, QueryMeta)> {
+        let path = format!("/v1/catalog/connect/{}", service);
+        self.get_with_meta(&path, Some(tag_params(tags)), q).await
+    }
+
+    #[tracing::instrument]
+    async fn get_node(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Option<CatalogNode>> {
+        self.get_node_with_meta(node, q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn get_node_with_meta(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<CatalogNode>, QueryMeta)> {
+        self.get_with_empty_meta(format!("/v1/catalog/node/{}", node), None, q)
+            .await
+            .map(|(r, meta)| (r.flatten(), meta))
+    }
+
+    #[tracing::instrument]
+    async fn list_node_services(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Option<CatalogNodeServices>> {
+        self.list_node_services_with_meta(node, q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_node_services_with_meta(
+        &self,
+        node: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<CatalogNodeServices>, QueryMeta)> {
+        self.get_with_empty_meta(format!("/v1/catalog/node-services/{}", node), None, q)
+            .await
+            .map(|(r, meta)| (r.flatten(), meta))
     }
+
+    #[tracing::instrument]
+    async fn list_gateway_services(
+        &self,
+        gateway: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<GatewayService>> {
+        self.list_gateway_services_with_meta(gateway, q).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_gateway_services_with_meta(
+        &self,
+        gateway: &str,
+        q: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<GatewayService>, QueryMeta)> {
+        let path = format!("/v1/catalog/gateway-services/{}", gateway);
+        self.get_with_meta(&path, None, q).await
+    }
+}
+
+/// This function builds the query parameters filtering services by tags.
+fn tag_params(tags: &[&str]) -> Vec<(String, String)> {
+    tags.iter().map(|tag| (String::from("tag"), (*tag).to_owned())).collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{tag_params, GatewayService};
     use crate::{Catalog, Client, Config};
 
+    #[test]
+    fn test_tag_params() {
+        assert_eq!(
+            tag_params(&["v1", "primary"]),
+            [
+                (String::from("tag"), String::from("v1")),
+                (String::from("tag"), String::from("primary"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_gateway_service() {
+        let service: GatewayService = serde_json::from_str(
+            r#"{
+                "Gateway": { "Name": "ingress" },
+                "Service": { "Name": "web" },
+                "GatewayKind": "ingress-gateway",
+                "Port": 8080,
+                "Protocol": "http",
+                "Hosts": ["web.example.com"],
+                "CAFile": "",
+                "CertFile": "",
+                "KeyFile": "",
+                "SNI": "",
+                "FromWildcard": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(service.gateway.name, "ingress");
+        assert_eq!(service.service.name, "web");
+        assert_eq!(service.port, 8080);
+        assert_eq!(service.hosts, Some(vec![String::from("web.example.com")]));
+    }
+
     #[tokio::test]
     async fn test_list_datacenters() {
-        let config = Config::new_from_env();
+        let config = Config::new_from_env().unwrap();
         let client = Client::new(config);
-        let r = client.list_datacenters().await.unwrap();
+        let r = client.list_datacenters(None).await.unwrap();
         assert_eq!(r, ["dc1"]);
     }
 
     #[tokio::test]
     async fn test_list_datacenter_services() {
-        let config = Config::default();
+        let config = Config::new().unwrap();
         let client = Client::new(config);
         let r = client.list_datacenter_services(None).await.unwrap();
         assert_ne!(r.len(), 0);
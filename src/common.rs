@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 /// A service's weights, comonly used in response payloads from Consul.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
-#[serde(default)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default, rename_all = "PascalCase")]
 pub struct ServiceWeights {
-    passing: u32,
-    warning: u32,
+    /// The weight of the service while its checks are passing.
+    pub passing: u32,
+    /// The weight of the service while any of its checks are warning.
+    pub warning: u32,
 }
 
 /// A tagged address, commonly used in response payloads from Consul.
@@ -14,3 +18,70 @@ pub struct TaggedAddress {
     /// The port included with this address.
     pub port: u16,
 }
+
+/// A node within the cluster, commonly used in response payloads from Consul.
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct Node {
+    /// The ID of the node.
+    #[serde(rename = "ID")]
+    pub id: String,
+    /// The name of the node.
+    pub node: String,
+    /// The address of the node.
+    pub address: String,
+    /// The datacenter of the node.
+    pub datacenter: Option<String>,
+    /// The addresses tagged to the node, such as `lan` and `wan`.
+    #[serde(rename = "TaggedAddresses")]
+    pub taggedaddresses: Option<HashMap<String, String>>,
+    /// The metadata attached to the node.
+    pub meta: Option<HashMap<String, String>>,
+    #[serde(rename = "CreateIndex")]
+    pub createindex: u64,
+    #[serde(rename = "ModifyIndex")]
+    pub modifyindex: u64,
+}
+
+/// The status of a health check, or the aggregated status of several checks.
+///
+/// Statuses are ordered by severity, so the aggregated status of several checks
+/// is the maximum of their statuses.
+#[derive(
+    Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    /// The check is passing.
+    Passing,
+    /// The check is passing, but with warnings.
+    Warning,
+    /// The check is failing.
+    #[default]
+    Critical,
+    /// The node or service is in maintenance mode. Consul reports maintenance
+    /// checks as critical, so this status is only produced by aggregation.
+    Maintenance,
+}
+
+impl std::fmt::Display for HealthStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HealthStatus::Passing => "passing",
+            HealthStatus::Warning => "warning",
+            HealthStatus::Critical => "critical",
+            HealthStatus::Maintenance => "maintenance",
+        })
+    }
+}
+
+/// This function deserializes a `null` value as its default, which the agent
+/// returns in place of empty values.
+#[cfg(feature = "txn")]
+pub(crate) fn deserialize_null_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + serde::Deserialize<'de>,
+{
+    Ok(<Option<T> as serde::Deserialize>::deserialize(deserializer)?.unwrap_or_default())
+}
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use serde_json::Value;
 
-use crate::{sealed::Sealed, Client, ConsulResult, QueryOptions};
+use crate::{sealed::Sealed, Client, ConsulResult, QueryMeta, QueryOptions};
 
 /// Response payload for the [ConnectCA::get_ca_config] method.
 #[derive(Default, Serialize, Deserialize, Debug)]
@@ -67,6 +67,15 @@ pub trait ConnectCA: Sealed {
     /// [API documentation]: https://www.consul.io/api/connect/ca.html#list-ca-root-certificates
     async fn list_ca_root_certs(&self, options: Option<QueryOptions>) -> ConsulResult<CARootList>;
 
+    /// This method returns the current list of trusted CA root certificates,
+    /// along with the [QueryMeta] of the response.
+    ///
+    /// See [ConnectCA::list_ca_root_certs] for more information.
+    async fn list_ca_root_certs_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(CARootList, QueryMeta)>;
+
     /// This method returns the current CA configuration.
     ///
     /// For more information, see the relevant endpoint's [API documentation].
@@ -74,6 +83,15 @@ pub trait ConnectCA: Sealed {
     /// [API documentation]: https://www.consul.io/api/connect/ca.html#get-ca-configuration
     async fn get_ca_config(&self, options: Option<QueryOptions>) -> ConsulResult<CAConfig>;
 
+    /// This method returns the current CA configuration, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [ConnectCA::get_ca_config] for more information.
+    async fn get_ca_config_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(CAConfig, QueryMeta)>;
+
     /// This method updates the configuration for the CA.
     ///
     /// For more information, see the relevant endpoint's [API documentation].
@@ -90,12 +108,28 @@ pub trait ConnectCA: Sealed {
 impl ConnectCA for Client {
     #[tracing::instrument]
     async fn list_ca_root_certs(&self, options: Option<QueryOptions>) -> ConsulResult<CARootList> {
-        self.get("/v1/connect/ca/roots", options).await
+        self.list_ca_root_certs_with_meta(options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_ca_root_certs_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(CARootList, QueryMeta)> {
+        self.get_with_meta("/v1/connect/ca/roots", None, options).await
     }
 
     #[tracing::instrument]
     async fn get_ca_config(&self, options: Option<QueryOptions>) -> ConsulResult<CAConfig> {
-        self.get("/v1/connect/ca/configuration", options).await
+        self.get_ca_config_with_meta(options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn get_ca_config_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(CAConfig, QueryMeta)> {
+        self.get_with_meta("/v1/connect/ca/configuration", None, options).await
     }
 
     #[tracing::instrument]
@@ -28,20 +28,15 @@ pub struct UpsertIntentionPayload {
 }
 
 /// Used to specify action type in [UpsertIntentionPayload].
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub enum IntentionAction {
     #[serde(rename = "allow")]
     Allow,
     #[serde(rename = "deny")]
+    #[default]
     Deny,
 }
 
-impl Default for IntentionAction {
-    fn default() -> Self {
-        IntentionAction::Deny
-    }
-}
-
 /// L7 attributes datatype.
 ///
 /// This is used to specify the attributes for a L7 intention, as used by the
@@ -0,0 +1,156 @@
+use std::{future::Future, time::Duration};
+
+use crate::{
+    watch::{watch, WatchOptions, WatchStream},
+    Client, ConsulError, ConsulResult, KVPair, Lock, LockOptions, QueryOptions, SessionEntry, KV,
+};
+
+/// Options used to create a [LeaderElection].
+#[derive(Clone, Debug)]
+pub struct ElectionOptions {
+    /// The key campaigned on. The leader holds this key with its session.
+    pub key: String,
+    /// The identity of this candidate, which is published as the value of the
+    /// key while it is the leader.
+    pub identity: String,
+    /// The template used to create the session of this candidate. The TTL,
+    /// behavior and checks of the session determine when leadership is lost.
+    pub session_entry: SessionEntry,
+    /// The maximum time to wait on each blocking query while campaigning or
+    /// observing the leader.
+    pub wait_time: Duration,
+    /// The number of consecutive failed queries tolerated while monitoring
+    /// leadership, before it is considered lost.
+    pub monitor_retries: u32,
+    /// The query options used for requests made by the election.
+    pub query_options: QueryOptions,
+}
+
+impl Default for ElectionOptions {
+    fn default() -> Self {
+        let lock = LockOptions::default();
+        ElectionOptions {
+            key: String::new(),
+            identity: String::new(),
+            session_entry: SessionEntry {
+                name: Some(String::from("Consul API Leader Election")),
+                ..lock.session_entry
+            },
+            wait_time: lock.wait_time,
+            monitor_retries: lock.monitor_retries,
+            query_options: lock.query_options,
+        }
+    }
+}
+
+/// A leader election between candidates campaigning on a single key.
+///
+/// Candidates campaign by acquiring the key with a session, as described in
+/// Consul's [leader election] guide. The elected leader publishes its
+/// identity in the value of the key, which followers can observe using
+/// [LeaderElection::observe].
+///
+/// [leader election]: https://learn.hashicorp.com/tutorials/consul/application-leader-elections
+#[derive(Clone, Debug)]
+pub struct LeaderElection {
+    client: Client,
+    options: ElectionOptions,
+}
+
+/// Leadership held by this candidate, returned by [LeaderElection::campaign].
+///
+/// Leadership is held until [Leadership::resign] is called, it is dropped, or
+/// it is lost because the candidate's session was invalidated.
+#[derive(Debug)]
+pub struct Leadership {
+    lock: Lock,
+}
+
+impl LeaderElection {
+    /// This method creates a new leader election with the given options.
+    pub fn new(client: &Client, options: ElectionOptions) -> Self {
+        LeaderElection { client: client.clone(), options }
+    }
+
+    /// This method campaigns for leadership, waiting until this candidate is
+    /// elected.
+    pub async fn campaign(&self) -> ConsulResult<Leadership> {
+        let options = LockOptions {
+            key: self.options.key.clone(),
+            value: self.options.identity.clone().into_bytes(),
+            session_entry: self.options.session_entry.clone(),
+            wait_time: self.options.wait_time,
+            monitor_retries: self.options.monitor_retries,
+            query_options: self.options.query_options.clone(),
+            ..Default::default()
+        };
+        // without try_once the lock is waited on until acquired, so this
+        // only fails if that changes
+        let lock = Lock::acquire(&self.client, options)
+            .await?
+            .ok_or_else(|| ConsulError::AcquireFailed(self.options.key.clone()))?;
+        Ok(Leadership { lock })
+    }
+
+    /// This method returns the identity of the current leader, or `None` if
+    /// there is no leader.
+    pub async fn leader(&self) -> ConsulResult<Option<String>> {
+        let entries = self
+            .client
+            .get_entry(&self.options.key, Some(self.options.query_options.clone()))
+            .await?;
+        Ok(leader_of(&entries))
+    }
+
+    /// This method returns a stream which yields the identity of the current
+    /// leader every time it changes, or `None` while there is no leader.
+    pub fn observe(&self) -> WatchStream<Option<String>> {
+        let client = self.client.clone();
+        let key = self.options.key.clone();
+        let mut query_options = self.options.query_options.clone();
+        query_options.wait_time = Some(self.options.wait_time);
+        let options = WatchOptions { query_options, ..Default::default() };
+        watch(options, move |q| {
+            let client = client.clone();
+            let key = key.clone();
+            async move {
+                let (entries, meta) = client.get_entry_with_meta(&key, Some(q)).await?;
+                Ok((leader_of(&entries), meta))
+            }
+        })
+    }
+}
+
+impl Leadership {
+    /// This method returns the ID of the session holding leadership.
+    pub fn session(&self) -> &str {
+        self.lock.session()
+    }
+
+    /// This method returns `true` if leadership has been lost.
+    pub fn is_lost(&self) -> bool {
+        self.lock.is_lost()
+    }
+
+    /// This method returns a future which resolves once leadership is lost.
+    pub fn lost(&self) -> impl Future<Output = ()> + Send + 'static {
+        self.lock.lost()
+    }
+
+    /// This method steps down from leadership, releasing the key so another
+    /// candidate can be elected.
+    pub async fn resign(self) -> ConsulResult<()> {
+        self.lock.unlock().await
+    }
+}
+
+/// This function returns the identity published by the holder of the key, if
+/// it is held.
+fn leader_of(entries: &[KVPair]) -> Option<String> {
+    match entries.first() {
+        Some(pair) if pair.session.is_some() => {
+            Some(String::from_utf8_lossy(&pair.value).into_owned())
+        }
+        _ => None,
+    }
+}
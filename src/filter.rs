@@ -0,0 +1,304 @@
+use std::{fmt, ops::Not};
+
+/// A selector targeting a field of the data being filtered, such as
+/// `Service.Tags` or `ServiceMeta.env`.
+///
+/// Map keys which are not valid identifiers are selected using the index
+/// syntax, so `Selector::new("NodeMeta").key("rack id")` renders as
+/// `NodeMeta["rack id"]`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Selector(String);
+
+impl Selector {
+    /// This method creates a selector from the given field, which may contain
+    /// several `.` separated components.
+    pub fn new(field: &str) -> Self {
+        Selector(field.to_owned())
+    }
+
+    /// This method selects the given field of the selected value.
+    pub fn field(mut self, name: &str) -> Self {
+        self.0.push('.');
+        self.0.push_str(name);
+        self
+    }
+
+    /// This method selects the given key of the selected map.
+    pub fn key(mut self, key: &str) -> Self {
+        if is_identifier(key) {
+            self.field(key)
+        } else {
+            self.0.push('[');
+            self.0.push_str(&quote(key));
+            self.0.push(']');
+            self
+        }
+    }
+
+    /// This method matches if the selected value is equal to the given value.
+    pub fn equals(self, value: &str) -> Filter {
+        self.compare("==", value)
+    }
+
+    /// This method matches if the selected value is not equal to the given
+    /// value.
+    pub fn not_equals(self, value: &str) -> Filter {
+        self.compare("!=", value)
+    }
+
+    /// This method matches if the selected value is empty.
+    pub fn is_empty(self) -> Filter {
+        Filter(Expr::Match(format!("{} is empty", self.0)))
+    }
+
+    /// This method matches if the selected value is not empty.
+    pub fn is_not_empty(self) -> Filter {
+        Filter(Expr::Match(format!("{} is not empty", self.0)))
+    }
+
+    /// This method matches if the given value is an element of the selected
+    /// list, or a key of the selected map, rendering as `"value" in Selector`.
+    pub fn includes(self, value: &str) -> Filter {
+        Filter(Expr::Match(format!("{} in {}", quote(value), self.0)))
+    }
+
+    /// This method matches if the given value is not an element of the
+    /// selected list, or a key of the selected map.
+    pub fn excludes(self, value: &str) -> Filter {
+        Filter(Expr::Match(format!("{} not in {}", quote(value), self.0)))
+    }
+
+    /// This method matches if the selected list or map contains the given
+    /// value, or if the selected string contains it as a substring.
+    pub fn contains(self, value: &str) -> Filter {
+        self.compare("contains", value)
+    }
+
+    /// This method matches if the selected value does not contain the given
+    /// value.
+    pub fn not_contains(self, value: &str) -> Filter {
+        self.compare("not contains", value)
+    }
+
+    /// This method matches if the selected string matches the given regular
+    /// expression.
+    pub fn matches(self, pattern: &str) -> Filter {
+        self.compare("matches", pattern)
+    }
+
+    /// This method matches if the selected string does not match the given
+    /// regular expression.
+    pub fn not_matches(self, pattern: &str) -> Filter {
+        self.compare("not matches", pattern)
+    }
+
+    fn compare(self, operator: &str, value: &str) -> Filter {
+        Filter(Expr::Match(format!("{} {} {}", self.0, operator, quote(value))))
+    }
+}
+
+impl From<&str> for Selector {
+    fn from(field: &str) -> Self {
+        Selector::new(field)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A filter expression, used as the [QueryOptions::filter] of a query.
+///
+/// Expressions are built from the matching methods of [Selector], and
+/// combined with [Filter::and], [Filter::or] and `!`. Values are always
+/// quoted and escaped, and combined expressions are parenthesized as needed.
+///
+/// ```
+/// use consul_oxide::{QueryOptions, Selector};
+///
+/// let filter = Selector::new("Service.Tags")
+///     .contains("primary")
+///     .and(Selector::new("Service.Meta").key("env").equals("prod"))
+///     .and(!Selector::new("Node.Node").matches("^canary-"));
+/// assert_eq!(
+///     filter.to_string(),
+///     r#"Service.Tags contains "primary" and Service.Meta.env == "prod" and not Node.Node matches "^canary-""#
+/// );
+/// let options = QueryOptions { filter: Some(filter.to_string()), ..Default::default() };
+/// ```
+///
+/// [QueryOptions::filter]: crate::QueryOptions::filter
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Filter(Expr);
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum Expr {
+    /// A single matching expression, already rendered.
+    Match(String),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+impl Filter {
+    /// This method combines two filters, matching if both match.
+    pub fn and(self, other: Filter) -> Filter {
+        Filter(match (self.0, other.0) {
+            (Expr::And(mut left), Expr::And(right)) => {
+                left.extend(right);
+                Expr::And(left)
+            }
+            (Expr::And(mut left), right) => {
+                left.push(right);
+                Expr::And(left)
+            }
+            (left, right) => Expr::And(vec![left, right]),
+        })
+    }
+
+    /// This method combines two filters, matching if either matches.
+    pub fn or(self, other: Filter) -> Filter {
+        Filter(match (self.0, other.0) {
+            (Expr::Or(mut left), Expr::Or(right)) => {
+                left.extend(right);
+                Expr::Or(left)
+            }
+            (Expr::Or(mut left), right) => {
+                left.push(right);
+                Expr::Or(left)
+            }
+            (left, right) => Expr::Or(vec![left, right]),
+        })
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Filter {
+        Filter(match self.0 {
+            Expr::Not(expr) => *expr,
+            expr => Expr::Not(Box::new(expr)),
+        })
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<Filter> for String {
+    fn from(filter: Filter) -> Self {
+        filter.to_string()
+    }
+}
+
+impl Expr {
+    /// This method writes the expression, wrapping it in parentheses unless
+    /// it is a single matching expression or a negation.
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Match(_) | Expr::Not(_) => write!(f, "{}", self),
+            _ => write!(f, "({})", self),
+        }
+    }
+
+    fn fmt_joined(f: &mut fmt::Formatter<'_>, exprs: &[Expr], operator: &str) -> fmt::Result {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                write!(f, " {} ", operator)?;
+            }
+            expr.fmt_operand(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Match(expr) => f.write_str(expr),
+            Expr::And(exprs) => Expr::fmt_joined(f, exprs, "and"),
+            Expr::Or(exprs) => Expr::fmt_joined(f, exprs, "or"),
+            Expr::Not(expr) => {
+                f.write_str("not ")?;
+                expr.fmt_operand(f)
+            }
+        }
+    }
+}
+
+/// This function returns `true` if the given key can be selected using the
+/// dotted syntax.
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// This function quotes the given value as a double-quoted string.
+fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selector;
+
+    #[test]
+    fn test_operators() {
+        let tags = || Selector::new("Service.Tags");
+        assert_eq!(Selector::new("Node").equals("a").to_string(), r#"Node == "a""#);
+        assert_eq!(Selector::new("Node").not_equals("a").to_string(), r#"Node != "a""#);
+        assert_eq!(tags().is_empty().to_string(), "Service.Tags is empty");
+        assert_eq!(tags().is_not_empty().to_string(), "Service.Tags is not empty");
+        assert_eq!(tags().includes("v1").to_string(), r#""v1" in Service.Tags"#);
+        assert_eq!(tags().excludes("v1").to_string(), r#""v1" not in Service.Tags"#);
+        assert_eq!(tags().contains("v1").to_string(), r#"Service.Tags contains "v1""#);
+        assert_eq!(tags().not_contains("v1").to_string(), r#"Service.Tags not contains "v1""#);
+        assert_eq!(Selector::new("Node").matches("^a").to_string(), r#"Node matches "^a""#);
+        assert_eq!(Selector::new("Node").not_matches("^a").to_string(), r#"Node not matches "^a""#);
+    }
+
+    #[test]
+    fn test_quoting() {
+        assert_eq!(
+            Selector::new("Node").equals("say \"hi\"\\\n").to_string(),
+            r#"Node == "say \"hi\"\\\n""#
+        );
+        assert_eq!(
+            Selector::new("NodeMeta").key("rack id").key("env").equals("a").to_string(),
+            r#"NodeMeta["rack id"].env == "a""#
+        );
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        let a = || Selector::new("A").equals("a");
+        let b = || Selector::new("B").equals("b");
+        let c = || Selector::new("C").equals("c");
+        assert_eq!(a().and(b()).and(c()).to_string(), r#"A == "a" and B == "b" and C == "c""#);
+        assert_eq!(a().or(b()).and(c()).to_string(), r#"(A == "a" or B == "b") and C == "c""#);
+        assert_eq!(a().and(b().or(c())).to_string(), r#"A == "a" and (B == "b" or C == "c")"#);
+        assert_eq!((!a().and(b())).to_string(), r#"not (A == "a" and B == "b")"#);
+        assert_eq!((!!a()).to_string(), r#"A == "a""#);
+        assert_eq!(a().or(!b()).to_string(), r#"A == "a" or not B == "b""#);
+    }
+}
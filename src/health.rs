@@ -1,43 +1,85 @@
-use std::collections::HashMap;
-
 use async_trait::async_trait;
 
-use crate::{sealed::Sealed, AgentService, Client, ConsulResult, QueryOptions};
+use crate::{
+    sealed::Sealed, AgentService, Client, ConsulResult, HealthStatus, Node, QueryMeta, QueryOptions,
+};
+
+/// The ID of the check registered while a node is in maintenance mode.
+const NODE_MAINTENANCE_CHECK: &str = "_node_maintenance";
+
+/// The prefix of the IDs of checks registered while a service is in
+/// maintenance mode.
+const SERVICE_MAINTENANCE_CHECK_PREFIX: &str = "_service_maintenance:";
 
-/// A registered service health check. Returned with its associated
-/// [ServiceEntry] instance by [Health::list_service_instances].
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+/// A registered health check, returned by the methods of the [Health] trait.
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct HealthCheck {
+    /// The name of the node the check is registered on.
     pub node: String,
+    /// The ID of the check.
     #[serde(rename = "CheckID")]
     pub check_id: String,
+    /// The name of the check.
     pub name: String,
-    pub status: String,
+    /// The status of the check.
+    pub status: HealthStatus,
+    /// Human readable notes about the check.
     pub notes: String,
+    /// The output of the last run of the check.
     pub output: String,
+    /// The ID of the service the check is associated with, if any.
     #[serde(rename = "ServiceID")]
     pub service_id: String,
+    /// The name of the service the check is associated with, if any.
+    #[serde(rename = "ServiceName")]
     pub servicename: String,
+    /// The tags of the service the check is associated with, if any.
+    #[serde(rename = "ServiceTags")]
     pub servicetags: Option<Vec<String>>,
+    /// The type of the check, such as `http` or `ttl`.
+    #[serde(rename = "Type")]
+    pub check_type: String,
+    #[serde(rename = "CreateIndex")]
+    pub createindex: Option<u64>,
+    #[serde(rename = "ModifyIndex")]
+    pub modifyindex: Option<u64>,
 }
 
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
-#[serde(default, rename_all = "PascalCase")]
-pub struct Node {
-    #[serde(rename = "ID")]
-    pub id: String,
-    pub node: String,
-    pub address: String,
-    pub datacenter: Option<String>,
-    pub taggedaddresses: Option<HashMap<String, String>>,
-    pub meta: Option<HashMap<String, String>>,
-    pub createindex: u64,
-    pub modifyindex: u64,
+impl HealthCheck {
+    /// This method returns `true` if the check was registered because its node
+    /// or service is in maintenance mode.
+    pub fn is_maintenance(&self) -> bool {
+        self.check_id == NODE_MAINTENANCE_CHECK
+            || self.check_id.starts_with(SERVICE_MAINTENANCE_CHECK_PREFIX)
+    }
+
+    /// This method returns the aggregated status of the given checks, the way
+    /// Consul computes it: maintenance if any check is a maintenance check,
+    /// otherwise the most severe status of the checks. An empty set of checks
+    /// is passing.
+    pub fn aggregate_status<'a, I>(checks: I) -> HealthStatus
+    where
+        I: IntoIterator<Item = &'a HealthCheck>,
+    {
+        checks
+            .into_iter()
+            .map(
+                |check| {
+                    if check.is_maintenance() {
+                        HealthStatus::Maintenance
+                    } else {
+                        check.status
+                    }
+                },
+            )
+            .max()
+            .unwrap_or(HealthStatus::Passing)
+    }
 }
 
 /// An [AgentService] with its associated [HealthCheck]s.
-#[derive(Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, Eq, Default, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct ServiceEntry {
     /// The node the service is associated with.
@@ -48,69 +90,397 @@ pub struct ServiceEntry {
     pub checks: Vec<HealthCheck>,
 }
 
+impl ServiceEntry {
+    /// This method returns the aggregated status of the checks of the instance,
+    /// which include the checks of its node.
+    ///
+    /// See [HealthCheck::aggregate_status] for more information.
+    pub fn aggregated_status(&self) -> HealthStatus {
+        HealthCheck::aggregate_status(&self.checks)
+    }
+
+    /// This method returns `true` if all checks of the instance are passing.
+    pub fn is_passing(&self) -> bool {
+        self.aggregated_status() == HealthStatus::Passing
+    }
+}
+
 /// This trait provides methods for interacting with the `/health` endpoints.
+///
+/// All methods support the `near`, `node_meta` and `filter` fields of
+/// [QueryOptions], for sorting and filtering the results.
 #[async_trait]
 pub trait Health: Sealed {
-    /// This endpoint returns the checks associated with the service provided on
-    /// the path.
+    /// This method returns the checks registered on the given node.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/health#list-checks-for-node
+    async fn list_node_checks(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>>;
+
+    /// This method returns the checks registered on the given node, along with
+    /// the [QueryMeta] of the response.
+    ///
+    /// See [Health::list_node_checks] for more information.
+    async fn list_node_checks_with_meta(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)>;
+
+    /// This method returns the checks associated with the given service.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/health#list-checks-for-service
+    async fn list_service_checks(
+        &self,
+        service: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>>;
+
+    /// This method returns the checks associated with the given service, along
+    /// with the [QueryMeta] of the response.
+    ///
+    /// See [Health::list_service_checks] for more information.
+    async fn list_service_checks_with_meta(
+        &self,
+        service: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)>;
+
+    /// This method returns the checks in the given state, which is one of
+    /// `passing`, `warning`, `critical` or `any`.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/health#list-checks-in-state
+    async fn list_checks_in_state(
+        &self,
+        state: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>>;
+
+    /// This method returns the checks in the given state, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [Health::list_checks_in_state] for more information.
+    async fn list_checks_in_state_with_meta(
+        &self,
+        state: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)>;
+
+    /// This method returns the instances of the given service, along with
+    /// their nodes and checks. Only instances with all of the given tags are
+    /// returned, and if `passing_only` is set, only instances whose checks are
+    /// all passing.
     ///
-    /// For more information, see the relevant endpoint's [API documentation].
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
     ///
     /// [API documentation]: https://www.consul.io/api-docs/health#list-nodes-for-service
     async fn list_service_instances(
         &self,
         service: &str,
-        tag: Option<&str>,
+        tags: &[&str],
         passing_only: bool,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<ServiceEntry>>;
+
+    /// This method returns the instances of the given service, along with the
+    /// [QueryMeta] of the response, allowing it to be used for blocking
+    /// queries.
+    ///
+    /// See [Health::list_service_instances] for more information.
+    async fn list_service_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)>;
+
+    /// This method returns the Connect-capable instances of the given service,
+    /// which are either Connect proxies for the service or natively
+    /// Connect-capable instances of it.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/health#list-nodes-for-connect-capable-service
+    async fn list_connect_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<ServiceEntry>>;
+
+    /// This method returns the Connect-capable instances of the given service,
+    /// along with the [QueryMeta] of the response.
+    ///
+    /// See [Health::list_connect_instances] for more information.
+    async fn list_connect_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)>;
+
+    /// This method returns the ingress gateways associated with the given
+    /// service.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/health#list-nodes-for-ingress-gateways-associated-to-a-service
+    async fn list_ingress_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<ServiceEntry>>;
+
+    /// This method returns the ingress gateways associated with the given
+    /// service, along with the [QueryMeta] of the response.
+    ///
+    /// See [Health::list_ingress_instances] for more information.
+    async fn list_ingress_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)>;
 }
 
 #[async_trait]
 impl Health for Client {
+    #[tracing::instrument]
+    async fn list_node_checks(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>> {
+        self.list_node_checks_with_meta(node, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_node_checks_with_meta(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)> {
+        let path = format!("/v1/health/node/{}", node);
+        self.get_with_meta(&path, None, options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_service_checks(
+        &self,
+        service: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>> {
+        self.list_service_checks_with_meta(service, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_service_checks_with_meta(
+        &self,
+        service: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)> {
+        let path = format!("/v1/health/checks/{}", service);
+        self.get_with_meta(&path, None, options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_checks_in_state(
+        &self,
+        state: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<HealthCheck>> {
+        self.list_checks_in_state_with_meta(state, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_checks_in_state_with_meta(
+        &self,
+        state: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<HealthCheck>, QueryMeta)> {
+        let path = format!("/v1/health/state/{}", state);
+        self.get_with_meta(&path, None, options).await
+    }
+
     #[tracing::instrument]
     async fn list_service_instances(
         &self,
         service: &str,
-        tag: Option<&str>,
+        tags: &[&str],
         passing_only: bool,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<ServiceEntry>> {
-        let mut params = HashMap::new();
+        self.list_service_instances_with_meta(service, tags, passing_only, options)
+            .await
+            .map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_service_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)> {
         let path = format!("/v1/health/service/{}", service);
-        if passing_only {
-            params.insert(String::from("passing"), String::from("1"));
-        }
-        if let Some(tag) = tag {
-            params.insert(String::from("tag"), tag.to_owned());
-        }
-        self.get(&path, options).await
+        self.get_with_meta(&path, Some(instance_params(tags, passing_only)), options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_connect_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<ServiceEntry>> {
+        self.list_connect_instances_with_meta(service, tags, passing_only, options)
+            .await
+            .map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_connect_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)> {
+        let path = format!("/v1/health/connect/{}", service);
+        self.get_with_meta(&path, Some(instance_params(tags, passing_only)), options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_ingress_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<ServiceEntry>> {
+        self.list_ingress_instances_with_meta(service, tags, passing_only, options)
+            .await
+            .map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_ingress_instances_with_meta(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<ServiceEntry>, QueryMeta)> {
+        let path = format!("/v1/health/ingress/{}", service);
+        self.get_with_meta(&path, Some(instance_params(tags, passing_only)), options).await
     }
 }
 
+/// This function builds the parameters used to filter service instances.
+fn instance_params(tags: &[&str], passing_only: bool) -> Vec<(String, String)> {
+    let mut params = Vec::new();
+    if passing_only {
+        params.push((String::from("passing"), String::from("1")));
+    }
+    for tag in tags {
+        params.push((String::from("tag"), (*tag).to_owned()));
+    }
+    params
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{Client, Config, Health};
+    use super::instance_params;
+    use crate::{Client, Config, Health, HealthCheck, HealthStatus, ServiceEntry};
+
+    fn check(id: &str, status: HealthStatus) -> HealthCheck {
+        HealthCheck { check_id: id.to_owned(), status, ..Default::default() }
+    }
+
+    #[test]
+    fn test_aggregated_status() {
+        let mut entry = ServiceEntry::default();
+        assert_eq!(entry.aggregated_status(), HealthStatus::Passing);
+        entry.checks = vec![check("serfHealth", HealthStatus::Passing)];
+        assert!(entry.is_passing());
+        entry.checks.push(check("service:web", HealthStatus::Warning));
+        assert_eq!(entry.aggregated_status(), HealthStatus::Warning);
+        entry.checks.push(check("service:web:2", HealthStatus::Critical));
+        assert_eq!(entry.aggregated_status(), HealthStatus::Critical);
+        // maintenance checks are reported as critical, but take precedence
+        entry.checks.push(check("_service_maintenance:web", HealthStatus::Critical));
+        assert_eq!(entry.aggregated_status(), HealthStatus::Maintenance);
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let status: HealthStatus = serde_json::from_str("\"warning\"").unwrap();
+        assert_eq!(status, HealthStatus::Warning);
+        assert_eq!(serde_json::to_string(&HealthStatus::Critical).unwrap(), "\"critical\"");
+        assert!(HealthStatus::Passing < HealthStatus::Warning);
+        assert!(HealthStatus::Critical < HealthStatus::Maintenance);
+    }
+
+    #[test]
+    fn test_instance_params() {
+        assert!(instance_params(&[], false).is_empty());
+        assert_eq!(
+            instance_params(&["a", "b"], true),
+            vec![
+                (String::from("passing"), String::from("1")),
+                (String::from("tag"), String::from("a")),
+                (String::from("tag"), String::from("b")),
+            ]
+        );
+    }
 
     #[tokio::test]
     async fn test_list_service_instances() {
-        let config = Config::default();
+        let config = Config::new().unwrap();
         let client = Client::new(config);
         // An existing service for a agent in dev mode
-        let snodes = client
-            .list_service_instances("consul", Option::None, true, Option::None)
-            .await
-            .unwrap();
+        let snodes =
+            client.list_service_instances("consul", &[], true, Option::None).await.unwrap();
         {
             assert!(!snodes.is_empty(), "should have at least one Service Node");
         }
         // A non existing, should be empty
         let snodes = client
-            .list_service_instances("non-existing-service", Option::None, true, Option::None)
+            .list_service_instances("non-existing-service", &[], true, Option::None)
             .await
             .unwrap();
         {
             assert_eq!(snodes.len(), 0);
         }
+        // The consul service has no tags in dev mode, so filtering by one
+        // should return nothing
+        let snodes = client
+            .list_service_instances("consul", &["missing"], true, Option::None)
+            .await
+            .unwrap();
+        assert!(snodes.is_empty());
     }
 }
@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap};
+
+use async_trait::async_trait;
+
+use super::base64_value;
+use crate::{sealed::Sealed, Client, ConsulResult, KVPair, QueryOptions, KV};
+#[cfg(feature = "txn")]
+use crate::{ConsulError, KVTxnOp, KVTxnVerb, Txn, TxnOp};
+
+/// An entry in the JSON document produced by `consul kv export` and consumed
+/// by `consul kv import`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KVExportEntry {
+    /// The key of the entry.
+    pub key: String,
+    /// The flags of the entry.
+    #[serde(default)]
+    pub flags: u64,
+    /// The value of the entry, base64-encoded in the document.
+    #[serde(default, with = "base64_value")]
+    pub value: Vec<u8>,
+}
+
+impl From<KVPair> for KVExportEntry {
+    fn from(pair: KVPair) -> Self {
+        KVExportEntry { key: pair.key, flags: pair.flags.unwrap_or_default(), value: pair.value }
+    }
+}
+
+/// Options used by [KVExport::import_entries].
+#[derive(Clone, Debug, Default)]
+pub struct ImportOptions {
+    /// If set, nothing is written, and the changes which would have been made
+    /// are returned.
+    pub dry_run: bool,
+    /// If set, entries are written in a single transaction, which is applied
+    /// atomically. The import fails with [ConsulError::TxnTooLarge] if more
+    /// than [MAX_TXN_OPS](crate::MAX_TXN_OPS) entries would be written.
+    #[cfg(feature = "txn")]
+    pub transactional: bool,
+    /// The query options used for requests made by the import.
+    pub query_options: QueryOptions,
+}
+
+/// A change made, or which would be made in a dry run, to a key by
+/// [KVExport::import_entries].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KVImportChange {
+    /// The key did not exist, and is created.
+    Created(String),
+    /// The key existed with a different value or flags, and is updated.
+    Updated(String),
+    /// The key already existed with the same value and flags, and is skipped.
+    Unchanged(String),
+}
+
+/// This trait provides methods for migrating the contents of the KV store, in
+/// the format used by the `consul kv export` and `consul kv import` commands.
+///
+/// For more information, see the [CLI documentation].
+///
+/// [CLI documentation]: https://www.consul.io/commands/kv/export
+#[async_trait]
+pub trait KVExport: Sealed {
+    /// This method exports all keys sharing the given prefix. The entries can
+    /// be serialized as JSON to produce a document compatible with `consul kv
+    /// import`.
+    async fn export_entries(
+        &self,
+        prefix: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<KVExportEntry>>;
+
+    /// This method imports the given entries, such as those deserialized from
+    /// a document produced by `consul kv export`.
+    ///
+    /// The entries are first compared against the contents of the store, and
+    /// only created or updated entries are written. The comparison is
+    /// returned, allowing a dry run to be used as a diff.
+    async fn import_entries(
+        &self,
+        entries: &[KVExportEntry],
+        options: ImportOptions,
+    ) -> ConsulResult<Vec<KVImportChange>>;
+}
+
+#[async_trait]
+impl KVExport for Client {
+    #[tracing::instrument]
+    async fn export_entries(
+        &self,
+        prefix: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<KVExportEntry>> {
+        let entries = self.list_entries(prefix, options).await?;
+        Ok(entries.into_iter().map(KVExportEntry::from).collect())
+    }
+
+    #[tracing::instrument(skip(entries))]
+    async fn import_entries(
+        &self,
+        entries: &[KVExportEntry],
+        options: ImportOptions,
+    ) -> ConsulResult<Vec<KVImportChange>> {
+        // fetch the existing entries which could be overwritten, a prefix at a
+        // time
+        let mut existing: HashMap<String, KVExportEntry> = HashMap::new();
+        for prefix in common_prefixes(entries) {
+            let current = self.export_entries(prefix, Some(options.query_options.clone())).await?;
+            existing.extend(current.into_iter().map(|entry| (entry.key.clone(), entry)));
+        }
+        let changes = diff(&existing, entries);
+        if options.dry_run {
+            return Ok(changes);
+        }
+        let writes: Vec<&KVExportEntry> = entries
+            .iter()
+            .zip(&changes)
+            .filter(|(_, change)| !matches!(change, KVImportChange::Unchanged(_)))
+            .map(|(entry, _)| entry)
+            .collect();
+        #[cfg(feature = "txn")]
+        if options.transactional {
+            let ops = writes
+                .iter()
+                .map(|entry| {
+                    TxnOp::KV(KVTxnOp {
+                        verb: KVTxnVerb::Set,
+                        key: entry.key.clone(),
+                        value: Some(base64::encode(&entry.value)),
+                        flags: Some(entry.flags),
+                        index: None,
+                        session: None,
+                    })
+                })
+                .collect();
+            let response = self.execute_txn(ops, Some(options.query_options.clone())).await?;
+            if !response.is_committed() {
+                return Err(ConsulError::TxnRolledBack(response.errors));
+            }
+            return Ok(changes);
+        }
+        for entry in writes {
+            let pair = KVPair {
+                key: entry.key.clone(),
+                flags: Some(entry.flags),
+                value: entry.value.clone(),
+                ..Default::default()
+            };
+            self.put_entry(&pair, Some(options.query_options.clone())).await?;
+        }
+        Ok(changes)
+    }
+}
+
+/// This function returns the prefixes covering the keys of all the given
+/// entries, which are the longest prefixes shared by the keys under each
+/// top-level segment. Unrelated keys therefore never share the empty prefix,
+/// which would cover the whole store.
+fn common_prefixes(entries: &[KVExportEntry]) -> Vec<&str> {
+    let mut prefixes: BTreeMap<&str, &str> = BTreeMap::new();
+    for entry in entries {
+        let segment = entry.key.split('/').next().unwrap_or_default();
+        let prefix = *prefixes.entry(segment).or_insert(&entry.key);
+        let len = prefix
+            .char_indices()
+            .zip(entry.key.chars())
+            .find(|((_, a), b)| a != b)
+            .map_or(prefix.len().min(entry.key.len()), |((i, _), _)| i);
+        prefixes.insert(segment, &prefix[..len]);
+    }
+    prefixes.into_values().collect()
+}
+
+/// This function compares the entries being imported against the existing
+/// entries.
+fn diff(
+    existing: &HashMap<String, KVExportEntry>,
+    entries: &[KVExportEntry],
+) -> Vec<KVImportChange> {
+    entries
+        .iter()
+        .map(|entry| match existing.get(&entry.key) {
+            None => KVImportChange::Created(entry.key.clone()),
+            Some(current) if current == entry => KVImportChange::Unchanged(entry.key.clone()),
+            Some(_) => KVImportChange::Updated(entry.key.clone()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use super::{common_prefixes, diff, KVExportEntry, KVImportChange};
+
+    fn entry(key: &str, value: &str) -> KVExportEntry {
+        KVExportEntry { key: key.to_owned(), flags: 0, value: value.as_bytes().to_vec() }
+    }
+
+    #[test]
+    fn test_export_format() {
+        let document = json!([{ "key": "app/config", "flags": 42, "value": "dmFsdWU=" }]);
+        let entries: Vec<KVExportEntry> = serde_json::from_value(document.clone()).unwrap();
+        assert_eq!(entries[0], KVExportEntry { flags: 42, ..entry("app/config", "value") });
+        assert_eq!(serde_json::to_value(&entries).unwrap(), document);
+    }
+
+    #[test]
+    fn test_common_prefixes() {
+        assert!(common_prefixes(&[]).is_empty());
+        assert_eq!(common_prefixes(&[entry("app/a", "")]), ["app/a"]);
+        assert_eq!(common_prefixes(&[entry("app/a", ""), entry("app/b", "")]), ["app/"]);
+        assert_eq!(common_prefixes(&[entry("app/a", ""), entry("app", "")]), ["app"]);
+        // unrelated keys are fetched separately, instead of fetching everything
+        let entries = [entry("app/a", ""), entry("web/a", ""), entry("app/b/c", "")];
+        assert_eq!(common_prefixes(&entries), ["app/", "web/a"]);
+    }
+
+    #[test]
+    fn test_diff() {
+        let existing: HashMap<_, _> = vec![entry("a", "1"), entry("b", "2")]
+            .into_iter()
+            .map(|entry| (entry.key.clone(), entry))
+            .collect();
+        let changes = diff(&existing, &[entry("a", "1"), entry("b", "3"), entry("c", "4")]);
+        assert_eq!(
+            changes,
+            vec![
+                KVImportChange::Unchanged(String::from("a")),
+                KVImportChange::Updated(String::from("b")),
+                KVImportChange::Created(String::from("c")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use async_trait::async_trait;
+use reqwest::Method;
+
+use crate::{sealed::Sealed, Client, ConsulError, ConsulResult, QueryMeta, QueryOptions};
+
+mod export;
+mod typed;
+
+pub use export::*;
+pub use typed::*;
+
+/// A key-value pair within the Consul KV store.
+#[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize, Debug)]
+#[serde(default)]
+#[allow(clippy::upper_case_acronyms)]
+pub struct KVPair {
+    /// The key of the key-value pair.
+    #[serde(rename = "Key")]
+    pub key: String,
+    #[serde(rename = "CreateIndex")]
+    pub createindex: Option<u64>,
+    #[serde(rename = "ModifyIndex")]
+    pub modifyindex: Option<u64>,
+    #[serde(rename = "LockIndex")]
+    pub lockindex: Option<u64>,
+    #[serde(rename = "Flags")]
+    pub flags: Option<u64>,
+    /// The value of the key-value pair. Values are sent and stored as raw
+    /// bytes, and base64-encoded in JSON payloads.
+    #[serde(rename = "Value", with = "base64_value")]
+    pub value: Vec<u8>,
+    #[serde(rename = "Session")]
+    pub session: Option<String>,
+}
+
+/// (De)serialization of the base64-encoded `Value` field of a [KVPair].
+mod base64_value {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::encode(value))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        // keys without a value have a null value
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => base64::decode(value).map_err(D::Error::custom),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// This trait provides methods for interacting with the Consul KV store.
+#[async_trait]
+pub trait KV: Sealed {
+    // TODO: deprecate
+    async fn acquire_entry(&self, _: &KVPair, _: Option<QueryOptions>) -> ConsulResult<bool>;
+
+    /// This method deletes the specified key. Deleting a key which does not
+    /// exist succeeds, so `true` is returned unless the request fails.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/kv#delete-key
+    async fn delete_entry(&self, _: &str, _: Option<QueryOptions>) -> ConsulResult<bool>;
+
+    /// This method atomically deletes all keys sharing the given prefix,
+    /// returning `true` unless the request fails. To guard against wiping the
+    /// whole store by accident, an empty prefix results in
+    /// [ConsulError::EmptyKey].
+    ///
+    /// The method makes use of the `recurse` parameter used by the [delete key](https://www.consul.io/api-docs/kv#delete-key) endpoint.
+    async fn delete_entries(&self, _: &str, _: Option<QueryOptions>) -> ConsulResult<bool>;
+
+    /// This method returns the specified key. If no key exists at the given
+    /// path, an empty [Vec] is returned.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/kv#read-key
+    async fn get_entry(&self, _: &str, _: Option<QueryOptions>) -> ConsulResult<Vec<KVPair>>;
+
+    /// This method returns the specified key, along with the [QueryMeta] of the
+    /// response. Passing the returned index as [QueryOptions::wait_index]
+    /// turns subsequent calls into blocking queries.
+    ///
+    /// See [KV::get_entry] for more information.
+    async fn get_entry_with_meta(
+        &self,
+        _: &str,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<KVPair>, QueryMeta)>;
+
+    /// This method returns the raw value of the specified key, without any of
+    /// its metadata. If no key exists at the given path, `None` is returned.
+    ///
+    /// The method makes use of the `raw` parameter used by the [read key](https://www.consul.io/api-docs/kv#read-key) endpoint.
+    async fn get_entry_raw(
+        &self,
+        _: &str,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<Option<Vec<u8>>>;
+
+    /// This method returns the names of all keys sharing the given prefix,
+    /// without their values.
+    ///
+    /// If a separator is given, only keys up to and including the first
+    /// occurrence of the separator after the prefix are returned. For example,
+    /// listing `config/` with the separator `/` returns `config/a` and
+    /// `config/b/`, but not `config/b/c`, emulating a directory listing.
+    ///
+    /// The method makes use of the `keys` and `separator` parameters used by the [read key](https://www.consul.io/api-docs/kv#read-key) endpoint.
+    async fn list_keys(
+        &self,
+        _: &str,
+        separator: Option<&str>,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<String>>;
+
+    /// This method returns the names of all keys sharing the given prefix,
+    /// along with the [QueryMeta] of the response.
+    ///
+    /// See [KV::list_keys] for more information.
+    async fn list_keys_with_meta(
+        &self,
+        _: &str,
+        separator: Option<&str>,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<String>, QueryMeta)>;
+
+    /// This method returns a [Vec] of [KVPair]s for all keys sharing the given
+    /// prefix.
+    ///
+    /// The method makes use of the `recurse` parameter used by the [read key](https://www.consul.io/api-docs/kv#read-key) endpoint.
+    async fn list_entries(&self, _: &str, _: Option<QueryOptions>) -> ConsulResult<Vec<KVPair>>;
+
+    /// This method returns all keys sharing the given prefix, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [KV::list_entries] for more information.
+    async fn list_entries_with_meta(
+        &self,
+        _: &str,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<KVPair>, QueryMeta)>;
+
+    /// This method updates the value and flags of the specified key. If no key
+    /// exists at the given path, the key will be created.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/kv#create-update-key
+    async fn put_entry(&self, _: &KVPair, _: Option<QueryOptions>) -> ConsulResult<bool>;
+
+    /// This method updates the value of the specified key, but only if its
+    /// modify index matches the given index. An index of `0` only creates the
+    /// key if it does not already exist. Returns whether the write succeeded.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/kv#cas
+    async fn cas_entry(
+        &self,
+        _: &KVPair,
+        index: u64,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<bool>;
+
+    /// This method deletes the specified key, but only if its modify index
+    /// matches the given index. Returns `false` if the index did not match,
+    /// in which case the key is left untouched.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/kv#cas-1
+    async fn cas_delete_entry(
+        &self,
+        _: &str,
+        index: u64,
+        _: Option<QueryOptions>,
+    ) -> ConsulResult<bool>;
+
+    // TODO: deprecate
+    async fn release_entry(&self, _: &KVPair, _: Option<QueryOptions>) -> ConsulResult<bool>;
+}
+
+#[async_trait]
+impl KV for Client {
+    #[tracing::instrument]
+    async fn acquire_entry(
+        &self,
+        pair: &KVPair,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<bool> {
+        let mut params = Vec::new();
+        if let Some(i) = pair.flags {
+            if i != 0 {
+                params.push((String::from("flags"), i.to_string()));
+            }
+        }
+        if let Some(ref session) = pair.session {
+            params.push((String::from("acquire"), session.to_owned()));
+            let path = format!("/v1/kv/{}", pair.key);
+            self.put_raw(&path, pair.value.clone(), Some(params), options).await
+        } else {
+            Err(ConsulError::MissingParameter("session_flag".to_owned()))
+        }
+    }
+
+    #[tracing::instrument]
+    async fn delete_entry(&self, key: &str, options: Option<QueryOptions>) -> ConsulResult<bool> {
+        let path = format!("/v1/kv/{}", key);
+        self.delete(&path, None, options).await
+    }
+
+    #[tracing::instrument]
+    async fn delete_entries(
+        &self,
+        prefix: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<bool> {
+        if prefix.is_empty() {
+            return Err(ConsulError::EmptyKey);
+        }
+        let params = vec![(String::from("recurse"), String::from(""))];
+        let path = format!("/v1/kv/{}", prefix);
+        self.delete(&path, Some(params), options).await
+    }
+
+    #[tracing::instrument]
+    async fn get_entry(
+        &self,
+        key: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<KVPair>> {
+        self.get_entry_with_meta(key, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn get_entry_with_meta(
+        &self,
+        key: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<KVPair>, QueryMeta)> {
+        let path = format!("/v1/kv/{}", key);
+        // missing keys return a 404 with an empty body
+        self.get_with_empty_meta(&path, None, options)
+            .await
+            .map(|(r, meta): (Option<Vec<KVPair>>, _)| (r.unwrap_or_default(), meta))
+    }
+
+    #[tracing::instrument]
+    async fn get_entry_raw(
+        &self,
+        key: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Option<Vec<u8>>> {
+        let params = vec![(String::from("raw"), String::from(""))];
+        let path = format!("/v1/kv/{}", key);
+        self.get_raw(&path, Some(params), options).await
+    }
+
+    #[tracing::instrument]
+    async fn list_keys(
+        &self,
+        prefix: &str,
+        separator: Option<&str>,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<String>> {
+        self.list_keys_with_meta(prefix, separator, o).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_keys_with_meta(
+        &self,
+        prefix: &str,
+        separator: Option<&str>,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<String>, QueryMeta)> {
+        let mut params = vec![(String::from("keys"), String::from(""))];
+        if let Some(separator) = separator {
+            params.push((String::from("separator"), separator.to_owned()));
+        }
+        let path = format!("/v1/kv/{}", prefix);
+        // no matching keys return a 404 with an empty body
+        self.get_with_empty_meta(&path, Some(params), o)
+            .await
+            .map(|(r, meta): (Option<Vec<String>>, _)| (r.unwrap_or_default(), meta))
+    }
+
+    #[tracing::instrument]
+    async fn list_entries(
+        &self,
+        prefix: &str,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<Vec<KVPair>> {
+        self.list_entries_with_meta(prefix, o).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_entries_with_meta(
+        &self,
+        prefix: &str,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<KVPair>, QueryMeta)> {
+        // enable key mode
+        let params = vec![(String::from("recurse"), String::from(""))];
+        let path = format!("/v1/kv/{}", prefix);
+        // use send with empty as consul returns invalid json
+        self.send_with_empty_meta(Method::GET, path, Some(params), None as Option<()>, o)
+            .await
+            .map(|(r, meta): (Option<Vec<KVPair>>, _)| (r.unwrap_or_default(), meta))
+    }
+
+    #[tracing::instrument]
+    async fn put_entry(&self, pair: &KVPair, o: Option<QueryOptions>) -> ConsulResult<bool> {
+        let mut params = Vec::new();
+        if let Some(i) = pair.flags {
+            if i != 0 {
+                params.push((String::from("flags"), i.to_string()));
+            }
+        }
+        let path = format!("/v1/kv/{}", pair.key);
+        self.put_raw(&path, pair.value.clone(), Some(params), o).await
+    }
+
+    #[tracing::instrument]
+    async fn cas_entry(
+        &self,
+        pair: &KVPair,
+        index: u64,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<bool> {
+        let mut params = Vec::new();
+        if let Some(i) = pair.flags {
+            if i != 0 {
+                params.push((String::from("flags"), i.to_string()));
+            }
+        }
+        params.push((String::from("cas"), index.to_string()));
+        let path = format!("/v1/kv/{}", pair.key);
+        self.put_raw(&path, pair.value.clone(), Some(params), o).await
+    }
+
+    #[tracing::instrument]
+    async fn cas_delete_entry(
+        &self,
+        key: &str,
+        index: u64,
+        o: Option<QueryOptions>,
+    ) -> ConsulResult<bool> {
+        let params = vec![(String::from("cas"), index.to_string())];
+        let path = format!("/v1/kv/{}", key);
+        self.delete(&path, Some(params), o).await
+    }
+
+    #[tracing::instrument]
+    async fn release_entry(&self, pair: &KVPair, o: Option<QueryOptions>) -> ConsulResult<bool> {
+        let mut params = Vec::new();
+        if let Some(i) = pair.flags {
+            if i != 0 {
+                params.push((String::from("flags"), i.to_string()));
+            }
+        }
+        if let Some(ref session) = pair.session {
+            params.push((String::from("release"), session.to_owned()));
+            let path = format!("/v1/kv/{}", pair.key);
+            self.put_raw(&path, pair.value.clone(), Some(params), o).await
+        } else {
+            Err(ConsulError::MissingParameter("session_flag".to_owned()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::KVPair;
+
+    #[test]
+    fn test_value_encoding() {
+        let pair: KVPair =
+            serde_json::from_value(json!({ "Key": "key", "Value": "AJ+Slg==", "Flags": 42 }))
+                .unwrap();
+        assert_eq!(pair.value, vec![0, 159, 146, 150]);
+        assert_eq!(serde_json::to_value(&pair).unwrap()["Value"], "AJ+Slg==");
+
+        let empty: KVPair = serde_json::from_value(json!({ "Key": "key", "Value": null })).unwrap();
+        assert!(empty.value.is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{sealed::Sealed, Client, ConsulError, ConsulResult, KVPair, QueryOptions, KV};
+
+/// The error type returned by [Codec]s.
+pub type CodecError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A format used to encode and decode values stored in the KV store.
+///
+/// Codecs for JSON, YAML and TOML are provided by [Json], `Yaml` and `Toml`
+/// respectively. The latter two require the `yaml` and `toml` features.
+pub trait Codec {
+    /// This method encodes the given value.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError>;
+
+    /// This method decodes a value from the given bytes.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError>;
+}
+
+/// A [Codec] storing values as JSON.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+impl Codec for Json {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// A [Codec] storing values as YAML.
+#[cfg(feature = "yaml")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Yaml;
+
+#[cfg(feature = "yaml")]
+impl Codec for Yaml {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(serde_yaml::to_string(value)?.into_bytes())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        Ok(serde_yaml::from_slice(bytes)?)
+    }
+}
+
+/// A [Codec] storing values as TOML.
+#[cfg(feature = "toml")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Toml;
+
+#[cfg(feature = "toml")]
+impl Codec for Toml {
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, CodecError> {
+        Ok(toml::to_string(value)?.into_bytes())
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CodecError> {
+        Ok(toml::from_str(std::str::from_utf8(bytes)?)?)
+    }
+}
+
+/// This trait provides typed access to the KV store, encoding and decoding
+/// values with a [Codec].
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use consul_oxide::{Client, Config, Json, TypedKV};
+///
+/// let client = Client::new(Config::new().unwrap());
+/// let config =
+///     async { client.get_typed::<Json, HashMap<String, u32>>("app/config", None).await };
+/// ```
+#[async_trait]
+pub trait TypedKV: Sealed {
+    /// This method returns the value of the specified key, decoded using the
+    /// given codec. If no key exists at the given path, `None` is returned.
+    ///
+    /// See [KV::get_entry] for more information.
+    async fn get_typed<C: Codec, T: DeserializeOwned>(
+        &self,
+        key: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Option<T>>;
+
+    /// This method encodes the given value using the given codec, and stores it
+    /// in the specified key.
+    ///
+    /// See [KV::put_entry] for more information.
+    async fn put_typed<C: Codec, T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<bool>;
+}
+
+#[async_trait]
+impl TypedKV for Client {
+    async fn get_typed<C: Codec, T: DeserializeOwned>(
+        &self,
+        key: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Option<T>> {
+        match self.get_entry(key, options).await?.first() {
+            Some(pair) => decode::<C, T>(pair).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    async fn put_typed<C: Codec, T: Serialize + Sync>(
+        &self,
+        key: &str,
+        value: &T,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<bool> {
+        let value = C::encode(value)
+            .map_err(|source| ConsulError::EncodeValueError { key: key.to_owned(), source })?;
+        let pair = KVPair { key: key.to_owned(), value, ..Default::default() };
+        self.put_entry(&pair, options).await
+    }
+}
+
+/// This function decodes the value of the given pair using the given codec.
+pub(crate) fn decode<C: Codec, T: DeserializeOwned>(pair: &KVPair) -> ConsulResult<T> {
+    C::decode(&pair.value)
+        .map_err(|source| ConsulError::DecodeValueError { key: pair.key.clone(), source })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{decode, Codec, Json};
+    use crate::{ConsulError, KVPair};
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut value = HashMap::new();
+        value.insert(String::from("replicas"), 3);
+        let pair = KVPair {
+            key: String::from("app/config"),
+            value: Json::encode(&value).unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(decode::<Json, HashMap<String, u32>>(&pair).unwrap(), value);
+    }
+
+    #[test]
+    fn test_decode_error_includes_key() {
+        let pair = KVPair {
+            key: String::from("app/config"),
+            value: b"not json".to_vec(),
+            ..Default::default()
+        };
+        match decode::<Json, HashMap<String, u32>>(&pair) {
+            Err(ConsulError::DecodeValueError { key, .. }) => assert_eq!(key, "app/config"),
+            other => panic!("expected DecodeValueError, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml_round_trip() {
+        use super::Yaml;
+
+        let value = vec![String::from("a"), String::from("b")];
+        let bytes = Yaml::encode(&value).unwrap();
+        assert_eq!(Yaml::decode::<Vec<String>>(&bytes).unwrap(), value);
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml_round_trip() {
+        use super::Toml;
+
+        let mut value = HashMap::new();
+        value.insert(String::from("replicas"), 3);
+        let bytes = Toml::encode(&value).unwrap();
+        assert_eq!(Toml::decode::<HashMap<String, u32>>(&bytes).unwrap(), value);
+    }
+}
@@ -0,0 +1,222 @@
+use futures::{
+    future,
+    stream::{self, StreamExt},
+};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+
+use crate::{
+    watch::{watch, WatchOptions, WatchStream},
+    Client, ConsulResult, KVPair, KV,
+};
+
+/// A configuration source merging several KV prefixes, or layers, into a
+/// single nested document.
+///
+/// Each key under a layer becomes a field of the document, nested by the `/`
+/// separated segments of the key relative to the layer's prefix. Values are
+/// parsed as JSON if possible, so `3` and `true` become a number and a
+/// boolean, and are otherwise used as strings.
+///
+/// Layers are merged in order, with later layers taking precedence over
+/// earlier ones. Nested objects are merged recursively, while any other value
+/// replaces the value of earlier layers.
+///
+/// ```
+/// use consul_oxide::{Client, Config, LayeredConfig};
+///
+/// let client = Client::new(Config::new().unwrap());
+/// let layers = vec![String::from("global/"), String::from("dc1/"), String::from("service/web/")];
+/// let config = LayeredConfig::new(&client, layers, None);
+/// let document = async { config.load().await };
+/// ```
+#[derive(Clone, Debug)]
+pub struct LayeredConfig {
+    client: Client,
+    layers: Vec<String>,
+    options: WatchOptions,
+}
+
+impl LayeredConfig {
+    /// This method creates a new configuration source from the given layers,
+    /// in increasing order of precedence.
+    pub fn new(client: &Client, layers: Vec<String>, options: Option<WatchOptions>) -> Self {
+        let layers = layers
+            .into_iter()
+            .map(|layer| if layer.is_empty() || layer.ends_with('/') { layer } else { layer + "/" })
+            .collect();
+        LayeredConfig { client: client.clone(), layers, options: options.unwrap_or_default() }
+    }
+
+    /// This method loads and merges the current contents of all layers.
+    pub async fn load(&self) -> ConsulResult<Value> {
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for prefix in &self.layers {
+            let options = Some(self.options.query_options.clone());
+            layers.push(self.client.list_entries(prefix, options).await?);
+        }
+        Ok(merge_layers(&self.layers, &layers))
+    }
+
+    /// This method loads the merged contents of all layers, and deserializes
+    /// them into the given type.
+    pub async fn load_as<T: DeserializeOwned>(&self) -> ConsulResult<T> {
+        Ok(serde_json::from_value(self.load().await?)?)
+    }
+
+    /// This method watches all layers, yielding a new snapshot of the merged
+    /// document whenever any of them changes.
+    ///
+    /// The first snapshot is yielded once every layer has been loaded.
+    pub fn watch(&self) -> WatchStream<Value> {
+        let streams = self.layers.iter().cloned().enumerate().map(|(i, prefix)| {
+            let client = self.client.clone();
+            watch(self.options.clone(), move |q| {
+                let client = client.clone();
+                let prefix = prefix.clone();
+                async move { client.list_entries_with_meta(&prefix, Some(q)).await }
+            })
+            .map(move |result| (i, result))
+        });
+        let prefixes = self.layers.clone();
+        let mut latest: Vec<Option<Vec<KVPair>>> = vec![None; prefixes.len()];
+        let mut last = None;
+        stream::select_all(streams)
+            .filter_map(move |(i, result)| {
+                let next = match result {
+                    Ok(entries) => {
+                        latest[i] = Some(entries);
+                        let layers: Option<Vec<Vec<KVPair>>> = latest.iter().cloned().collect();
+                        layers.map(|layers| merge_layers(&prefixes, &layers)).and_then(|merged| {
+                            if last.as_ref() == Some(&merged) {
+                                return None;
+                            }
+                            last = Some(merged.clone());
+                            Some(Ok(merged))
+                        })
+                    }
+                    Err(err) => Some(Err(err)),
+                };
+                future::ready(next)
+            })
+            .boxed()
+    }
+
+    /// This method watches all layers, yielding a new snapshot deserialized
+    /// into the given type whenever any of them changes.
+    ///
+    /// See [LayeredConfig::watch] for more information.
+    pub fn watch_as<T: DeserializeOwned + Send + 'static>(&self) -> WatchStream<T> {
+        self.watch()
+            .map(|result| result.and_then(|value| Ok(serde_json::from_value(value)?)))
+            .boxed()
+    }
+}
+
+/// This function merges the entries of each layer, in increasing order of
+/// precedence.
+fn merge_layers(prefixes: &[String], layers: &[Vec<KVPair>]) -> Value {
+    let mut merged = Value::Object(Map::new());
+    for (prefix, entries) in prefixes.iter().zip(layers) {
+        merge(&mut merged, layer_document(prefix, entries));
+    }
+    merged
+}
+
+/// This function builds the nested document of a single layer.
+fn layer_document(prefix: &str, entries: &[KVPair]) -> Value {
+    let mut document = Value::Object(Map::new());
+    for entry in entries {
+        let relative = match entry.key.strip_prefix(prefix) {
+            Some(relative) => relative,
+            None => continue,
+        };
+        let segments: Vec<&str> = relative.split('/').filter(|s| !s.is_empty()).collect();
+        // skip directory keys, which only exist to group other keys
+        let (last, parents) = match segments.split_last() {
+            Some(split) if !entry.key.ends_with('/') => split,
+            _ => continue,
+        };
+        let mut node = &mut document;
+        for segment in parents {
+            node = object(node).entry(*segment).or_insert_with(|| Value::Object(Map::new()));
+        }
+        object(node).insert((*last).to_owned(), parse_value(&entry.value));
+    }
+    document
+}
+
+/// This function returns the given value as an object, replacing it with an
+/// empty object if it is not one.
+fn object(value: &mut Value) -> &mut Map<String, Value> {
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+    match value {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// This function parses a value as JSON, falling back to a string.
+fn parse_value(value: &[u8]) -> Value {
+    serde_json::from_slice(value)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(value).into_owned()))
+}
+
+/// This function recursively merges `overlay` into `base`.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::merge_layers;
+    use crate::KVPair;
+
+    fn pair(key: &str, value: &str) -> KVPair {
+        KVPair { key: key.to_owned(), value: value.as_bytes().to_vec(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_merge_layers() {
+        let prefixes = vec![String::from("global/"), String::from("service/web/")];
+        let layers = vec![
+            vec![
+                pair("global/", ""),
+                pair("global/db/host", "localhost"),
+                pair("global/db/port", "5432"),
+                pair("global/debug", "false"),
+            ],
+            vec![pair("service/web/db/host", "db.internal"), pair("service/web/debug", "true")],
+        ];
+        assert_eq!(
+            merge_layers(&prefixes, &layers),
+            json!({
+                "db": { "host": "db.internal", "port": 5432 },
+                "debug": true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_merge_replaces_non_objects() {
+        let prefixes = vec![String::from("a/"), String::from("b/")];
+        let layers = vec![vec![pair("a/db", "disabled")], vec![pair("b/db/host", "localhost")]];
+        assert_eq!(merge_layers(&prefixes, &layers), json!({ "db": { "host": "localhost" } }));
+    }
+}
@@ -26,7 +26,7 @@
 //! The `Client` struct provides the main entry point for the library.
 //! ```
 //! use consul_oxide::{Client, Config};
-//! let config = Config::default();
+//! let config = Config::new().unwrap();
 //! let client = Client::new(config);
 //! ```
 //! You can pass in custom configuration by using the `Config` datatype. By
@@ -37,8 +37,8 @@
 //! ```
 //! use consul_oxide::{Agent, Client, Config};
 //!
-//! let client = Client::new(Config::default());
-//! let agents = async { client.list_members(false).await };
+//! let client = Client::new(Config::new().unwrap());
+//! let agents = async { client.list_members(false, None).await };
 //! ```
 //!
 //! ## Async Support
@@ -47,48 +47,105 @@
 //! the `tokio` and `async-std` runtimes. At this time, there is no blocking API
 //! available. As an alternative, you can use versions of this library below
 //! `0.5.0`, as these are blocking.
+//!
+//! The exception to this are the [Watch] streams, which use Tokio's timer to
+//! back off after errors, and [Lock], [Semaphore], [Mirror] and
+//! [LoadBalancer], which run background tasks. These must be used from within
+//! a Tokio runtime.
 
 #![allow(unused_doc_comments)]
+// without any of the endpoint features, nothing sends requests through the
+// client
+#![cfg_attr(
+    not(any(
+        feature = "agent",
+        feature = "catalog",
+        feature = "connect",
+        feature = "health",
+        feature = "kv",
+        feature = "session"
+    )),
+    allow(dead_code)
+)]
 
 use thiserror::Error;
 
 #[macro_use]
 extern crate serde_derive;
 
-use std::{env, time::Duration};
-
-use reqwest::{Client as HttpClient, ClientBuilder};
+use std::{collections::HashMap, time::Duration};
 
 mod common;
+mod config;
 mod request;
 
+#[cfg(feature = "acl")]
 mod acl;
 #[cfg(feature = "agent")]
 mod agent;
+#[cfg(feature = "health")]
+mod balancer;
 #[cfg(feature = "catalog")]
 mod catalog;
 #[cfg(feature = "connect")]
 mod connect;
+#[cfg(all(feature = "kv", feature = "session"))]
+mod election;
+mod filter;
 #[cfg(feature = "health")]
 mod health;
 #[cfg(feature = "kv")]
 mod kv;
+#[cfg(feature = "kv")]
+mod layered;
+#[cfg(all(feature = "kv", feature = "session"))]
+mod lock;
+#[cfg(feature = "kv")]
+mod mirror;
+#[cfg(all(feature = "kv", feature = "session"))]
+mod semaphore;
 #[cfg(feature = "session")]
 mod session;
+#[cfg(feature = "txn")]
+mod txn;
+#[cfg(unix)]
+mod unix;
+#[cfg(any(feature = "catalog", feature = "health", feature = "kv"))]
+mod watch;
 
+#[cfg(feature = "acl")]
+pub use acl::*;
 #[cfg(feature = "agent")]
 pub use agent::*;
+#[cfg(feature = "health")]
+pub use balancer::*;
 #[cfg(feature = "catalog")]
 pub use catalog::*;
 pub use common::*;
+pub use config::*;
 #[cfg(feature = "connect")]
 pub use connect::*;
+#[cfg(all(feature = "kv", feature = "session"))]
+pub use election::*;
+pub use filter::*;
 #[cfg(feature = "health")]
 pub use health::*;
 #[cfg(feature = "kv")]
 pub use kv::*;
+#[cfg(feature = "kv")]
+pub use layered::*;
+#[cfg(all(feature = "kv", feature = "session"))]
+pub use lock::*;
+#[cfg(feature = "kv")]
+pub use mirror::*;
+#[cfg(all(feature = "kv", feature = "session"))]
+pub use semaphore::*;
 #[cfg(feature = "session")]
 pub use session::*;
+#[cfg(feature = "txn")]
+pub use txn::*;
+#[cfg(any(feature = "catalog", feature = "health", feature = "kv"))]
+pub use watch::*;
 
 /// The Consul client. This struct implements the various traits providing the
 /// various Consul endpoints, and is responsible for making requests to the
@@ -96,84 +153,42 @@ pub use session::*;
 #[derive(Clone, Debug)]
 pub struct Client {
     config: Config,
+    /// The socket requests are sent over, if the agent's address is a unix
+    /// socket address.
+    #[cfg(unix)]
+    socket: Option<unix::UnixSocket>,
 }
 
 impl Client {
     /// This method creates a new Consul client.
     pub fn new(config: Config) -> Self {
-        Client { config }
-    }
-}
-
-/// The configuration for the Consul client.
-#[derive(Clone, Debug)]
-pub struct Config {
-    /// The address of the Consul agent.
-    pub address: String,
-    /// The name of the datacenter this agent is hosted in.
-    pub datacenter: Option<String>,
-    /// The dedicated HTTP client to use for requests. Most users should not
-    /// need to modify this unless authentication is necessary.
-    pub http_client: HttpClient,
-    /// The Consul agent's access token.
-    pub token: Option<String>,
-    /// The timeout for requests.
-    pub wait_time: Option<Duration>,
-}
-
-impl Config {
-    /// This method creates a new default configuration for the Consul client
-    /// from the current environment.
-    ///
-    /// # Panics
-    /// Panics if `request::Client` construction fails.
-    pub fn new_from_env() -> Config {
-        let consul_addr = match env::var("CONSUL_HTTP_ADDR") {
-            Ok(val) => {
-                if val.starts_with("http") {
-                    val
-                } else {
-                    format!("http://{}", val)
-                }
-            }
-            Err(_e) => String::from("http://127.0.0.1:8500"),
-        };
-        let consul_token = env::var("CONSUL_HTTP_TOKEN").ok();
-        let client = ClientBuilder::new().build().unwrap();
-        Config {
-            address: consul_addr,
-            datacenter: None,
-            http_client: client,
-            token: consul_token,
-            wait_time: None,
+        Client {
+            #[cfg(unix)]
+            socket: unix::UnixSocket::from_address(&config.address),
+            config,
         }
     }
+}
 
-    /// This method creates a new `Config` with the given address.
-    ///
-    /// # Panics
-    /// Panics if `request::Client` construction fails.
-    pub fn new_from_consul_host(host: &str, port: Option<u16>, token: Option<String>) -> Config {
-        let client = ClientBuilder::new().build().unwrap();
-        Config {
-            address: format!("{}:{}", host, port.unwrap_or(8500)),
-            datacenter: None,
-            http_client: client,
-            token,
-            wait_time: None,
-        }
-    }
+/// The details of a request rejected by the Consul API.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RequestError {
+    /// The method of the request.
+    pub method: reqwest::Method,
+    /// The path of the endpoint the request was sent to.
+    pub endpoint: String,
+    /// The status code of the response.
+    pub status: reqwest::StatusCode,
+    /// The body of the response, which usually explains the error.
+    pub body: String,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        let client = ClientBuilder::new().build().unwrap();
-        Config {
-            address: String::from("http://127.0.0.1:8500"),
-            datacenter: None,
-            http_client: client,
-            token: None,
-            wait_time: None,
+impl std::fmt::Display for RequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} returned {}", self.method, self.endpoint, self.status)?;
+        match self.body.trim() {
+            "" => Ok(()),
+            body => write!(f, ": {}", body),
         }
     }
 }
@@ -181,11 +196,38 @@ impl Default for Config {
 /// Enumeration of API error types.
 #[derive(Debug, Error)]
 pub enum ConsulError {
-    /// The Consul API returned an error.
+    /// The request could not be sent, or its response could not be read.
     #[error("http request failed")]
     HttpError(#[from] reqwest::Error),
-    #[error("request failed with code {0}")]
-    RequestFailed(reqwest::StatusCode),
+    /// The TLS options are invalid, or their files could not be read.
+    #[error("invalid TLS configuration: {reason}")]
+    InvalidTlsConfig {
+        reason: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+    /// The address of the agent, or a URL built from it, is invalid.
+    #[error("invalid url {url}")]
+    InvalidUrl {
+        url: String,
+        #[source]
+        source: Option<url::ParseError>,
+    },
+    /// The request was denied by the ACL system.
+    #[error("permission denied: {0}")]
+    PermissionDenied(RequestError),
+    /// The requested resource does not exist.
+    #[error("not found: {0}")]
+    NotFound(RequestError),
+    /// The request was rejected by the agent's or servers' rate limits.
+    #[error("rate limited: {0}")]
+    RateLimited(RequestError),
+    /// The request could not be served because the cluster has no leader.
+    #[error("no cluster leader: {0}")]
+    LeaderUnavailable(RequestError),
+    /// The Consul API returned any other error.
+    #[error("request failed: {0}")]
+    RequestFailed(RequestError),
     /// A parameter was not provided.
     #[error("missing parameter, {0}")]
     MissingParameter(String),
@@ -193,6 +235,84 @@ pub enum ConsulError {
     EmptyKey,
     #[error("failed to decode response body")]
     DecodeError(#[from] serde_json::Error),
+    /// A local file operation failed.
+    #[error("i/o error")]
+    IoError(#[from] std::io::Error),
+    /// The key used by a lock is already in use by something other than a
+    /// lock.
+    #[error("key {0} is already in use by something other than a lock")]
+    LockConflict(String),
+    /// A key could not be acquired with a session.
+    #[error("failed to acquire key {0}")]
+    AcquireFailed(String),
+    /// A key under the prefix used by a semaphore is already in use by
+    /// something other than a semaphore.
+    #[error("key {0} is already in use by something other than a semaphore")]
+    SemaphoreConflict(String),
+    /// The limit of a semaphore does not match the limit of its existing
+    /// holders.
+    #[error("semaphore limit {limit} does not match existing limit {existing}")]
+    SemaphoreLimitConflict { limit: u32, existing: u32 },
+    /// A transaction contains more operations than the agent allows.
+    #[error("transaction contains {0} operations, but at most 64 are allowed")]
+    TxnTooLarge(usize),
+    /// A transaction was rolled back because some of its operations failed.
+    #[cfg(feature = "txn")]
+    #[error("transaction was rolled back: {0:?}")]
+    TxnRolledBack(Vec<TxnError>),
+    /// The value of a key could not be decoded by a codec.
+    #[error("failed to decode value of key {key}")]
+    DecodeValueError {
+        key: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    /// A value could not be encoded by a codec before being stored in a key.
+    #[error("failed to encode value of key {key}")]
+    EncodeValueError {
+        key: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+impl ConsulError {
+    /// This method returns the details of the rejected request, if the error
+    /// was returned by the Consul API.
+    pub fn request_error(&self) -> Option<&RequestError> {
+        match self {
+            ConsulError::PermissionDenied(err)
+            | ConsulError::NotFound(err)
+            | ConsulError::RateLimited(err)
+            | ConsulError::LeaderUnavailable(err)
+            | ConsulError::RequestFailed(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// This method returns the status code of the response, if the error was
+    /// returned by the Consul API.
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        self.request_error().map(|err| err.status)
+    }
+}
+
+/// The consistency mode of a read query. For more information, see the [API
+/// documentation].
+///
+/// [API documentation]: https://www.consul.io/api-docs/features/consistency
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ConsistencyMode {
+    /// The query is served by the leader, which may return stale results in
+    /// the rare case of a leadership change.
+    #[default]
+    Default,
+    /// The query is served by the leader after confirming its leadership,
+    /// guaranteeing consistent results at the cost of an extra round trip.
+    Consistent,
+    /// The query may be served by any server, returning potentially stale
+    /// results. See [QueryOptions::max_stale] for bounding the staleness.
+    Stale,
 }
 
 /// Query options to fine tune requests made to the agent.
@@ -200,17 +320,91 @@ pub enum ConsulError {
 pub struct QueryOptions {
     /// The datacenter to query.
     pub datacenter: Option<String>,
+    /// The index to wait on. If set, the request becomes a blocking query,
+    /// which will not return until the index of the requested data exceeds
+    /// this value or `wait_time` elapses.
     pub wait_index: Option<u64>,
     /// The maximum time to wait for a query to complete.
     pub wait_time: Option<Duration>,
+    /// Sorts the returned nodes by estimated round trip time from the given
+    /// node. The special value `_agent` sorts by distance from the agent
+    /// serving the request.
+    pub near: Option<String>,
+    /// Only returns nodes with the given metadata. Multiple pairs are
+    /// combined with a logical AND.
+    pub node_meta: HashMap<String, String>,
+    /// An expression used to filter the results of the query, which can be
+    /// built using [Filter]. For more information, see the [filtering
+    /// documentation].
+    ///
+    /// [filtering documentation]: https://www.consul.io/api-docs/features/filtering
+    pub filter: Option<String>,
+    /// The consistency mode of the query.
+    pub consistency: ConsistencyMode,
+    /// The maximum staleness of the results of a query. If the server serving
+    /// the request is further behind the leader, the query is retried
+    /// against the leader. Setting this implies [ConsistencyMode::Stale],
+    /// unless the query is [ConsistencyMode::Consistent], in which case it is
+    /// ignored.
+    pub max_stale: Option<Duration>,
+    /// Whether the query may be served from the agent's cache, on endpoints
+    /// which support it. For more information, see the [caching
+    /// documentation].
+    ///
+    /// [caching documentation]: https://www.consul.io/api-docs/features/caching
+    pub cached: bool,
+    /// The maximum age of a cached result, sent as the `max-age` directive of
+    /// the `Cache-Control` header. Only used if `cached` is set.
+    pub max_age: Option<Duration>,
+    /// How long a cached result may be served for past its maximum age if
+    /// refreshing it fails, sent as the `stale-if-error` directive of the
+    /// `Cache-Control` header. Only used if `cached` is set.
+    pub stale_if_error: Option<Duration>,
+    /// The namespace to query.
+    #[cfg(feature = "enterprise")]
+    pub namespace: Option<String>,
+    /// The admin partition to query.
+    #[cfg(feature = "enterprise")]
+    pub partition: Option<String>,
+    /// The cluster peer to query, for resources imported from a peered
+    /// cluster.
+    pub peer: Option<String>,
+    /// The ACL token sent with the request, instead of [Config::token]. This
+    /// allows a single client to act on behalf of several tokens.
+    pub token: Option<String>,
+}
+
+/// Metadata returned by the agent alongside the response to a read query.
+///
+/// The `last_index` field can be passed back as [QueryOptions::wait_index] to
+/// perform a blocking query. For more information, see the [API
+/// documentation].
+///
+/// [API documentation]: https://www.consul.io/api-docs/features/blocking
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct QueryMeta {
+    /// The index of the returned data, as reported by the `X-Consul-Index`
+    /// header.
+    pub last_index: Option<u64>,
+    /// Whether the cluster had a known leader when the request was served.
+    pub known_leader: bool,
+    /// The time since the server serving the request last contacted the
+    /// leader.
+    pub last_contact: Option<Duration>,
+    /// Whether the response was served from the agent's cache. This is `None`
+    /// if the agent did not report on its cache.
+    pub cache_hit: Option<bool>,
+    /// The age of the cached response, if it was served from the agent's
+    /// cache.
+    pub cache_age: Option<Duration>,
 }
 
 /// Type alias for `Result<T, ConsulError>`.
 pub type ConsulResult<T> = Result<T, ConsulError>;
 
+/// Internal module to prevent re-implementation of strictly client-related
+/// traits.
 pub(crate) mod sealed {
-    ///! Internal module to prevent re-implementation of strictly
-    /// client-related traits.
     use crate::Client;
 
     pub trait Sealed {}
@@ -0,0 +1,397 @@
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use tokio::{
+    sync::watch::{channel, Receiver, Sender},
+    task::JoinHandle,
+};
+
+use crate::{
+    watch::{next_index, watch, WatchOptions},
+    Client, ConsulError, ConsulResult, KVPair, QueryOptions, Session, SessionEntry, KV,
+};
+
+/// The flags value set on keys held by a [Lock]. This is used to detect keys
+/// which are being used by a different recipe, such as a semaphore.
+pub const LOCK_FLAG_VALUE: u64 = 0x2ddccbc058a50c18;
+
+/// The TTL used for sessions created by a [Lock] if none is given.
+const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(15);
+
+/// Options used to acquire a [Lock].
+#[derive(Clone, Debug)]
+pub struct LockOptions {
+    /// The key used to hold the lock.
+    pub key: String,
+    /// The value stored in the key while the lock is held.
+    pub value: Vec<u8>,
+    /// The ID of an existing session to hold the lock with. If `None`, a
+    /// session is created from `session_entry`, renewed in the background
+    /// while the lock is held, and destroyed when the lock is released.
+    pub session: Option<String>,
+    /// The template used to create a session when `session` is `None`. If no
+    /// TTL is set, a TTL of 15 seconds is used.
+    pub session_entry: SessionEntry,
+    /// The maximum time to wait on each blocking query while waiting for the
+    /// lock.
+    pub wait_time: Duration,
+    /// The time to wait before retrying when the lock is free, but cannot be
+    /// acquired due to the lock delay of the previous holder.
+    pub retry_time: Duration,
+    /// If set, the lock is only waited on for up to `wait_time`, after which
+    /// [Lock::acquire] gives up.
+    pub try_once: bool,
+    /// The number of consecutive failed queries tolerated while monitoring a
+    /// held lock, before the lock is considered lost.
+    pub monitor_retries: u32,
+    /// The query options used for requests made by the lock.
+    pub query_options: QueryOptions,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        LockOptions {
+            key: String::new(),
+            value: Vec::new(),
+            session: None,
+            session_entry: SessionEntry {
+                name: Some(String::from("Consul API Lock")),
+                behavior: Some(String::from("release")),
+                ..Default::default()
+            },
+            wait_time: Duration::from_secs(15),
+            retry_time: Duration::from_secs(5),
+            try_once: false,
+            monitor_retries: 0,
+            query_options: QueryOptions::default(),
+        }
+    }
+}
+
+/// A distributed lock held on a key in the KV store.
+///
+/// This implements Consul's [leader election] recipe: a session is used to
+/// acquire the key, and the lock is held for as long as the session is valid
+/// and holds the key. The lock is released when [Lock::unlock] is called or
+/// the lock is dropped.
+///
+/// Renewing the session and monitoring the lock is done in background tasks,
+/// so a lock must be acquired from within a Tokio runtime.
+///
+/// [leader election]: https://learn.hashicorp.com/tutorials/consul/application-leader-elections
+#[derive(Debug)]
+pub struct Lock {
+    client: Client,
+    pair: KVPair,
+    owns_session: bool,
+    query_options: QueryOptions,
+    lost: Receiver<bool>,
+    monitor: JoinHandle<()>,
+    renewal: Option<JoinHandle<()>>,
+    released: bool,
+}
+
+impl Lock {
+    /// This method waits until the lock described by the given options is
+    /// acquired.
+    ///
+    /// If `try_once` is set and the lock could not be acquired within
+    /// `wait_time`, `None` is returned. An error is returned if the key is
+    /// already in use by something other than a lock.
+    pub async fn acquire(client: &Client, options: LockOptions) -> ConsulResult<Option<Lock>> {
+        if options.key.is_empty() {
+            return Err(ConsulError::EmptyKey);
+        }
+        let (lost_tx, lost) = channel(false);
+        let lost_tx = Arc::new(lost_tx);
+        // create a session if none was provided
+        let (session, renewal) = match &options.session {
+            Some(session) => (session.clone(), None),
+            None => {
+                let (session, renewal) = create_renewed_session(
+                    client,
+                    &options.session_entry,
+                    &options.query_options,
+                    lost_tx.clone(),
+                )
+                .await?;
+                (session, Some(renewal))
+            }
+        };
+        let pair = KVPair {
+            key: options.key.clone(),
+            value: options.value.clone(),
+            flags: Some(LOCK_FLAG_VALUE),
+            session: Some(session.clone()),
+            ..Default::default()
+        };
+        match wait_for_key(client, &pair, &options).await {
+            Ok(true) => {}
+            result => {
+                if let Some(renewal) = renewal {
+                    renewal.abort();
+                    // preserve the original error if the session cannot be destroyed
+                    let _ =
+                        client.destroy_session(&session, Some(options.query_options.clone())).await;
+                }
+                return result.map(|_| None);
+            }
+        }
+        let key = pair.key.clone();
+        let monitor = spawn_monitor(
+            client,
+            &pair.key,
+            options.monitor_retries,
+            &options.query_options,
+            lost_tx,
+            move |entries| {
+                let held = entries.iter().find(|entry| entry.key == key);
+                held.and_then(|entry| entry.session.as_ref()) == Some(&session)
+            },
+        );
+        Ok(Some(Lock {
+            client: client.clone(),
+            pair,
+            owns_session: renewal.is_some(),
+            query_options: options.query_options,
+            lost,
+            monitor,
+            renewal,
+            released: false,
+        }))
+    }
+
+    /// This method returns the ID of the session holding the lock.
+    pub fn session(&self) -> &str {
+        self.pair.session.as_deref().unwrap_or_default()
+    }
+
+    /// This method returns `true` if the lock has been lost, either because
+    /// the session was invalidated or the key was released by another party.
+    pub fn is_lost(&self) -> bool {
+        *self.lost.borrow()
+    }
+
+    /// This method returns a future which resolves once the lock is lost.
+    pub fn lost(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut lost = self.lost.clone();
+        async move {
+            // an error means the lock was released, so it is no longer held
+            let _ = lost.wait_for(|lost| *lost).await;
+        }
+    }
+
+    /// This method releases the lock, and destroys its session if it was
+    /// created by the lock.
+    pub async fn unlock(mut self) -> ConsulResult<()> {
+        self.released = true;
+        self.monitor.abort();
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+        release(&self.client, &self.pair, self.owns_session, &self.query_options).await
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        self.monitor.abort();
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+        // release the lock in the background if a runtime is still available
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let client = self.client.clone();
+            let pair = self.pair.clone();
+            let owns_session = self.owns_session;
+            let options = self.query_options.clone();
+            handle.spawn(async move {
+                if let Err(err) = release(&client, &pair, owns_session, &options).await {
+                    tracing::warn!("failed to release lock on {}: {}", pair.key, err);
+                }
+            });
+        }
+    }
+}
+
+/// This function waits until the given pair is acquired with its session.
+/// Returns `false` if the lock is in `try_once` mode and the wait time
+/// elapsed.
+async fn wait_for_key(client: &Client, pair: &KVPair, options: &LockOptions) -> ConsulResult<bool> {
+    let deadline = Instant::now() + options.wait_time;
+    let mut index = 0;
+    let mut attempted = false;
+    let mut delayed = false;
+    loop {
+        let mut query = options.query_options.clone();
+        query.wait_time = Some(options.wait_time);
+        if options.try_once && attempted {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            query.wait_time = Some(remaining);
+        }
+        query.wait_index = if index > 0 { Some(index) } else { None };
+        let (entries, meta) = client.get_entry_with_meta(&pair.key, Some(query)).await?;
+        index = next_index(index, meta.last_index.unwrap_or(0));
+        attempted = true;
+        if let Some(existing) = entries.into_iter().next() {
+            if existing.flags != Some(LOCK_FLAG_VALUE) {
+                return Err(ConsulError::LockConflict(pair.key.clone()));
+            }
+            match existing.session {
+                // already held by this session
+                Some(session) if Some(&session) == pair.session.as_ref() => return Ok(true),
+                // held by someone else, so wait for it to change
+                Some(_) => {
+                    delayed = false;
+                    continue;
+                }
+                None => {}
+            }
+        }
+        // the lock is free, but the previous holder's lock delay may apply
+        if delayed {
+            let mut retry_time = options.retry_time;
+            if options.try_once {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Ok(false);
+                }
+                retry_time = retry_time.min(remaining);
+            }
+            tokio::time::sleep(retry_time).await;
+        }
+        if client.acquire_entry(pair, Some(options.query_options.clone())).await? {
+            return Ok(true);
+        }
+        delayed = true;
+        index = 0;
+    }
+}
+
+/// This function releases the given pair, destroying its session if it is
+/// owned by the caller.
+async fn release(
+    client: &Client,
+    pair: &KVPair,
+    owns_session: bool,
+    options: &QueryOptions,
+) -> ConsulResult<()> {
+    client.release_entry(pair, Some(options.clone())).await?;
+    if owns_session {
+        client
+            .destroy_session(pair.session.as_deref().unwrap_or_default(), Some(options.clone()))
+            .await?;
+    }
+    Ok(())
+}
+
+/// This function creates a session from the given template, and spawns a task
+/// which renews it until aborted. If the session can no longer be renewed,
+/// `lost` is set.
+pub(crate) async fn create_renewed_session(
+    client: &Client,
+    entry: &SessionEntry,
+    options: &QueryOptions,
+    lost: Arc<Sender<bool>>,
+) -> ConsulResult<(String, JoinHandle<()>)> {
+    let mut entry = entry.clone();
+    // the agent only accepts whole seconds between 10s and 24h, and the session
+    // must be renewed against the TTL it was actually created with
+    let ttl = entry.ttl_duration().unwrap_or(DEFAULT_SESSION_TTL).as_secs().clamp(10, 86400);
+    let ttl = Duration::from_secs(ttl);
+    entry.ttl = Some(format!("{}s", ttl.as_secs()));
+    let session = client.create_session(entry, Some(options.clone())).await?;
+    let id = session.id.ok_or_else(|| ConsulError::MissingParameter(String::from("ID")))?;
+    let renewal =
+        tokio::spawn(renew_session(client.clone(), id.clone(), ttl, options.clone(), lost));
+    Ok((id, renewal))
+}
+
+/// This function periodically renews the given session, until it fails to do
+/// so for longer than the session's TTL.
+async fn renew_session(
+    client: Client,
+    id: String,
+    mut ttl: Duration,
+    options: QueryOptions,
+    lost: Arc<Sender<bool>>,
+) {
+    let mut renewed = Instant::now();
+    let mut delay = ttl / 2;
+    loop {
+        tokio::time::sleep(delay).await;
+        match client.renew_session(&id, Some(options.clone())).await {
+            Ok(entries) => match entries.first() {
+                Some(entry) => {
+                    // the server may have increased the TTL
+                    ttl = entry.ttl_duration().unwrap_or(ttl);
+                    renewed = Instant::now();
+                    delay = ttl / 2;
+                }
+                None => break,
+            },
+            Err(err) => {
+                tracing::warn!("failed to renew session {}: {}", id, err);
+                if renewed.elapsed() >= ttl {
+                    break;
+                }
+                delay = Duration::from_secs(1).min(ttl / 2);
+            }
+        }
+    }
+    lost.send_replace(true);
+}
+
+/// This function spawns a task which watches the given KV prefix, and sets
+/// `lost` once `held` returns `false` for its entries, or more than `retries`
+/// consecutive queries fail.
+pub(crate) fn spawn_monitor<F>(
+    client: &Client,
+    prefix: &str,
+    retries: u32,
+    options: &QueryOptions,
+    lost: Arc<Sender<bool>>,
+    held: F,
+) -> JoinHandle<()>
+where
+    F: Fn(&[KVPair]) -> bool + Send + 'static,
+{
+    let client = client.clone();
+    let prefix = prefix.to_owned();
+    let options = WatchOptions { query_options: options.clone(), ..Default::default() };
+    tokio::spawn(async move {
+        let mut stream = watch(options, move |q| {
+            let client = client.clone();
+            let prefix = prefix.clone();
+            async move { client.list_entries_with_meta(&prefix, Some(q)).await }
+        });
+        let mut failures = 0;
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(entries) => {
+                    failures = 0;
+                    if !held(&entries) {
+                        break;
+                    }
+                }
+                Err(_) => {
+                    failures += 1;
+                    if failures > retries {
+                        break;
+                    }
+                }
+            }
+        }
+        lost.send_replace(true);
+    })
+}
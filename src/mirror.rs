@@ -0,0 +1,473 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::StreamExt;
+use tokio::{
+    fs,
+    sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+    time::MissedTickBehavior,
+};
+
+use crate::{
+    watch::{watch, WatchOptions},
+    Client, ConsulError, ConsulResult, KVPair, QueryOptions, KV,
+};
+
+/// The suffix of temporary files written by a [Mirror] before they are renamed
+/// into place. These files are ignored when scanning for local changes.
+const TEMP_SUFFIX: &str = ".consul-mirror.tmp";
+
+/// Options used to start a [Mirror].
+#[derive(Clone, Debug)]
+pub struct MirrorOptions {
+    /// The prefix of the keys to mirror.
+    pub prefix: String,
+    /// The directory the keys are mirrored into. It is created if it does not
+    /// exist.
+    pub directory: PathBuf,
+    /// If set, files created, modified or deleted in the directory are pushed
+    /// back to the KV store.
+    pub push: bool,
+    /// The interval at which the directory is scanned for local changes when
+    /// `push` is set, and at which files which could not be written are
+    /// retried.
+    pub scan_interval: Duration,
+    /// The options used to watch the prefix for changes.
+    pub watch_options: WatchOptions,
+}
+
+impl Default for MirrorOptions {
+    fn default() -> Self {
+        MirrorOptions {
+            prefix: String::new(),
+            directory: PathBuf::new(),
+            push: false,
+            scan_interval: Duration::from_secs(1),
+            watch_options: WatchOptions::default(),
+        }
+    }
+}
+
+/// An event emitted by a [Mirror].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MirrorEvent {
+    /// The key was created or updated in the KV store, and written to its
+    /// file.
+    Pulled(String),
+    /// The key was deleted from the KV store, and its file removed.
+    Removed(String),
+    /// The key's file was created or modified, and written to the KV store.
+    Pushed(String),
+    /// The key's file was deleted, and the key deleted from the KV store.
+    Deleted(String),
+    /// The key was changed both locally and in the KV store. The KV store
+    /// always wins, so the local change is overwritten.
+    Conflict(String),
+}
+
+/// A mirror of a KV prefix in a local directory, with one file per key.
+///
+/// The prefix is watched using blocking queries, and files are written
+/// atomically by renaming a temporary file into place, so readers never see
+/// partially written files. Keys ending in `/` are mirrored as directories.
+///
+/// If [MirrorOptions::push] is set, local changes are pushed back using
+/// check-and-set writes against the modify index the file was last synced at.
+/// If the key was modified in the meantime the write is rejected, a
+/// [MirrorEvent::Conflict] is emitted, and the file is overwritten with the
+/// newer value. The store also wins when the mirror is first started, so
+/// existing files for keys in the store are overwritten.
+///
+/// The mirror runs in a background task until it is dropped, so it must be
+/// started from within a Tokio runtime.
+#[derive(Debug)]
+pub struct Mirror {
+    events: UnboundedReceiver<ConsulResult<MirrorEvent>>,
+    task: JoinHandle<()>,
+}
+
+/// The last synced state of a key.
+struct MirroredKey {
+    /// The modify index of the key when it was last synced.
+    index: u64,
+    /// The value of the key when it was last synced.
+    value: Vec<u8>,
+}
+
+impl Mirror {
+    /// This method starts mirroring the prefix described by the given options.
+    pub async fn start(client: &Client, options: MirrorOptions) -> ConsulResult<Mirror> {
+        if options.directory.as_os_str().is_empty() {
+            return Err(ConsulError::MissingParameter(String::from("directory")));
+        }
+        fs::create_dir_all(&options.directory).await?;
+        let mut options = options;
+        if !options.prefix.is_empty() && !options.prefix.ends_with('/') {
+            options.prefix.push('/');
+        }
+        let (tx, events) = unbounded_channel();
+        let task = tokio::spawn(run(client.clone(), options, tx));
+        Ok(Mirror { events, task })
+    }
+
+    /// This method waits for the next event emitted by the mirror. Errors do
+    /// not stop the mirror, which retries after backing off.
+    ///
+    /// Events are buffered until they are received, so a long-running mirror
+    /// should have its events drained.
+    pub async fn next_event(&mut self) -> Option<ConsulResult<MirrorEvent>> {
+        self.events.recv().await
+    }
+}
+
+impl Drop for Mirror {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// This function runs the mirror until its task is aborted.
+async fn run(
+    client: Client,
+    options: MirrorOptions,
+    events: UnboundedSender<ConsulResult<MirrorEvent>>,
+) {
+    let mut state = HashMap::new();
+    let mut stream = {
+        let client = client.clone();
+        let prefix = options.prefix.clone();
+        watch(options.watch_options.clone(), move |q| {
+            let client = client.clone();
+            let prefix = prefix.clone();
+            async move { client.list_entries_with_meta(&prefix, Some(q)).await }
+        })
+    };
+    let mut scan = tokio::time::interval(options.scan_interval);
+    scan.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // local changes are only detected once the initial state has been pulled
+    let mut synced = false;
+    // entries which could not be written to their files, retried on every scan
+    let mut failed = Vec::new();
+    loop {
+        tokio::select! {
+            result = stream.next() => match result {
+                Some(Ok(entries)) => {
+                    failed = pull(&options, &mut state, entries, &events).await;
+                    synced = true;
+                }
+                Some(Err(err)) => {
+                    let _ = events.send(Err(err));
+                }
+                None => break,
+            },
+            _ = scan.tick(), if synced && (options.push || !failed.is_empty()) => {
+                for pair in std::mem::take(&mut failed) {
+                    failed.extend(pull_entry(&options, &mut state, pair, &events).await);
+                }
+                if options.push {
+                    push(&client, &options, &mut state, &events).await;
+                }
+            }
+        }
+    }
+}
+
+/// This function writes the changed entries to their files, and removes the
+/// files of deleted keys. Returns the entries which could not be written.
+async fn pull(
+    options: &MirrorOptions,
+    state: &mut HashMap<String, MirroredKey>,
+    entries: Vec<KVPair>,
+    events: &UnboundedSender<ConsulResult<MirrorEvent>>,
+) -> Vec<KVPair> {
+    let mut seen = HashSet::new();
+    let mut failed = Vec::new();
+    for pair in entries {
+        if !pair.key.ends_with('/') {
+            seen.insert(pair.key.clone());
+        }
+        failed.extend(pull_entry(options, state, pair, events).await);
+    }
+    let removed: Vec<String> = state.keys().filter(|key| !seen.contains(*key)).cloned().collect();
+    for key in removed {
+        state.remove(&key);
+        if let Some(path) = key_path(&options.directory, &options.prefix, &key) {
+            match fs::remove_file(&path).await {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    let _ = events.send(Err(err.into()));
+                }
+                _ => {
+                    let _ = events.send(Ok(MirrorEvent::Removed(key)));
+                }
+            }
+        }
+    }
+    failed
+}
+
+/// This function writes an entry to its file if it changed since it was last
+/// synced. Returns the entry if it could not be written, so it can be retried.
+async fn pull_entry(
+    options: &MirrorOptions,
+    state: &mut HashMap<String, MirroredKey>,
+    pair: KVPair,
+    events: &UnboundedSender<ConsulResult<MirrorEvent>>,
+) -> Option<KVPair> {
+    let path = match key_path(&options.directory, &options.prefix, &pair.key) {
+        Some(path) => path,
+        None => {
+            tracing::warn!("not mirroring key {} as it is not a valid path", pair.key);
+            return None;
+        }
+    };
+    if pair.key.ends_with('/') {
+        if let Err(err) = fs::create_dir_all(&path).await {
+            let _ = events.send(Err(err.into()));
+            return Some(pair);
+        }
+        return None;
+    }
+    let index = pair.modifyindex.unwrap_or_default();
+    if let Some(previous) = state.get(&pair.key) {
+        if previous.index == index {
+            return None;
+        }
+        // a local change which was not pushed is about to be overwritten
+        if let Ok(local) = fs::read(&path).await {
+            if local != previous.value && local != pair.value {
+                let _ = events.send(Ok(MirrorEvent::Conflict(pair.key.clone())));
+            }
+        }
+    }
+    if let Err(err) = write_atomic(&path, &pair.value).await {
+        let _ = events.send(Err(err.into()));
+        return Some(pair);
+    }
+    let _ = events.send(Ok(MirrorEvent::Pulled(pair.key.clone())));
+    state.insert(pair.key, MirroredKey { index, value: pair.value });
+    None
+}
+
+/// This function writes files which changed since they were last synced to
+/// the KV store, and deletes the keys of deleted files.
+async fn push(
+    client: &Client,
+    options: &MirrorOptions,
+    state: &mut HashMap<String, MirroredKey>,
+    events: &UnboundedSender<ConsulResult<MirrorEvent>>,
+) {
+    let query_options = Some(options.watch_options.query_options.clone());
+    let files = match scan(&options.directory).await {
+        Ok(files) => files,
+        Err(err) => {
+            let _ = events.send(Err(err.into()));
+            return;
+        }
+    };
+    let mut present = HashSet::new();
+    for (relative, path) in files {
+        let key = format!("{}{}", options.prefix, relative);
+        present.insert(key.clone());
+        let value = match fs::read(&path).await {
+            Ok(value) => value,
+            // the file may have been removed since the scan
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                let _ = events.send(Err(err.into()));
+                continue;
+            }
+        };
+        // new files are only created if the key does not exist yet
+        let index = match state.get(&key) {
+            Some(previous) if previous.value == value => continue,
+            Some(previous) => previous.index,
+            None => 0,
+        };
+        let pair = KVPair { key: key.clone(), value, ..Default::default() };
+        match client.cas_entry(&pair, index, query_options.clone()).await {
+            Ok(true) => {
+                let index = match written_index(client, &pair, query_options.clone()).await {
+                    Ok(written) => written.unwrap_or(index),
+                    Err(err) => {
+                        let _ = events.send(Err(err));
+                        index
+                    }
+                };
+                state.insert(key.clone(), MirroredKey { index, value: pair.value });
+                let _ = events.send(Ok(MirrorEvent::Pushed(key)));
+            }
+            Ok(false) => {
+                let _ = events.send(Ok(MirrorEvent::Conflict(key)));
+            }
+            Err(err) => {
+                let _ = events.send(Err(err));
+            }
+        }
+    }
+    let deleted: Vec<(String, u64)> = state
+        .iter()
+        .filter(|(key, _)| !present.contains(*key))
+        .map(|(key, previous)| (key.clone(), previous.index))
+        .collect();
+    for (key, index) in deleted {
+        match client.cas_delete_entry(&key, index, query_options.clone()).await {
+            Ok(true) => {
+                state.remove(&key);
+                let _ = events.send(Ok(MirrorEvent::Deleted(key)));
+            }
+            Ok(false) => {
+                let _ = events.send(Ok(MirrorEvent::Conflict(key)));
+            }
+            Err(err) => {
+                let _ = events.send(Err(err));
+            }
+        }
+    }
+}
+
+/// This function returns the modify index of a pair which was just written, so
+/// the watch does not mistake the write for a remote change. Returns `None` if
+/// the key has since been changed by someone else, in which case that change
+/// is pulled as usual.
+async fn written_index(
+    client: &Client,
+    pair: &KVPair,
+    options: Option<QueryOptions>,
+) -> ConsulResult<Option<u64>> {
+    let entries = client.get_entry(&pair.key, options).await?;
+    Ok(entries
+        .into_iter()
+        .next()
+        .filter(|entry| entry.value == pair.value)
+        .and_then(|entry| entry.modifyindex))
+}
+
+/// This function returns the path of the file mirroring the given key, or
+/// `None` if the key cannot be safely mapped to a path within the directory.
+fn key_path(directory: &Path, prefix: &str, key: &str) -> Option<PathBuf> {
+    let relative = key.strip_prefix(prefix)?;
+    let mut path = directory.to_path_buf();
+    for component in relative.trim_end_matches('/').split('/') {
+        if component.is_empty()
+            || component == "."
+            || component == ".."
+            || component.contains('\\')
+            || component.ends_with(TEMP_SUFFIX)
+        {
+            return None;
+        }
+        path.push(component);
+    }
+    Some(path)
+}
+
+/// This function writes the given contents to a temporary file, then renames it
+/// to the given path, so the file is replaced atomically.
+async fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}{}", name, TEMP_SUFFIX));
+    fs::write(&temp, contents).await?;
+    fs::rename(&temp, path).await
+}
+
+/// This function recursively lists the files in the given directory, returning
+/// their paths relative to the directory using `/` as the separator.
+async fn scan(directory: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![(String::new(), directory.to_path_buf())];
+    while let Some((relative, dir)) = pending.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = match entry.file_name().into_string() {
+                Ok(name) if !name.ends_with(TEMP_SUFFIX) => name,
+                _ => continue,
+            };
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                pending.push((format!("{}{}/", relative, name), entry.path()));
+            } else if file_type.is_file() {
+                files.push((format!("{}{}", relative, name), entry.path()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use super::{key_path, pull, pull_entry, scan, write_atomic, MirrorEvent, MirrorOptions};
+    use crate::KVPair;
+
+    #[test]
+    fn test_key_path() {
+        let dir = Path::new("/mirror");
+        assert_eq!(key_path(dir, "app/", "app/a"), Some(dir.join("a")));
+        assert_eq!(key_path(dir, "app/", "app/a/b"), Some(dir.join("a").join("b")));
+        assert_eq!(key_path(dir, "app/", "app/a/"), Some(dir.join("a")));
+        assert_eq!(key_path(dir, "app/", "web/a"), None);
+        assert_eq!(key_path(dir, "app/", "app/"), None);
+        assert_eq!(key_path(dir, "app/", "app/../a"), None);
+        assert_eq!(key_path(dir, "app/", "app/a//b"), None);
+    }
+
+    #[tokio::test]
+    async fn test_write_and_scan() {
+        let dir = std::env::temp_dir().join(format!("consul-mirror-{}", std::process::id()));
+        write_atomic(&dir.join("a"), b"a").await.unwrap();
+        write_atomic(&dir.join("b").join("c"), b"c").await.unwrap();
+        let mut files = scan(&dir).await.unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                (String::from("a"), dir.join("a")),
+                (String::from("b/c"), dir.join("b").join("c"))
+            ]
+        );
+        assert_eq!(std::fs::read(dir.join("b").join("c")).unwrap(), b"c");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pull_retries_failed_writes() {
+        let dir = std::env::temp_dir().join(format!("consul-mirror-retry-{}", std::process::id()));
+        let options = MirrorOptions {
+            prefix: String::from("app/"),
+            directory: dir.clone(),
+            ..Default::default()
+        };
+        let (tx, mut events) = unbounded_channel();
+        let mut state = HashMap::new();
+        let pair = KVPair {
+            key: String::from("app/a"),
+            value: b"a".to_vec(),
+            modifyindex: Some(10),
+            ..Default::default()
+        };
+
+        // a directory in the way of the file fails the write
+        std::fs::create_dir_all(dir.join("a")).unwrap();
+        let failed = pull(&options, &mut state, vec![pair.clone()], &tx).await;
+        assert_eq!(failed, std::slice::from_ref(&pair));
+        assert!(events.recv().await.unwrap().is_err());
+        assert!(state.is_empty());
+
+        std::fs::remove_dir(dir.join("a")).unwrap();
+        assert_eq!(pull_entry(&options, &mut state, pair.clone(), &tx).await, None);
+        assert_eq!(events.recv().await.unwrap().unwrap(), MirrorEvent::Pulled(pair.key));
+        assert_eq!(std::fs::read(dir.join("a")).unwrap(), b"a");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
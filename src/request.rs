@@ -1,86 +1,290 @@
-use std::{collections::HashMap, fmt::Debug, future::Future, str};
+use std::{fmt::Debug, str, time::Duration};
 
-use async_trait::async_trait;
-use reqwest::{Method, StatusCode};
+use reqwest::{header::HeaderMap, Method, RequestBuilder, Response as HttpResponse, StatusCode};
 use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
-use crate::{Client, ConsulError, ConsulResult, QueryOptions};
+use crate::{
+    Client, ConsistencyMode, ConsulError, ConsulResult, QueryMeta, QueryOptions, RequestError,
+};
 
-#[async_trait]
-trait AndThenAsync<T: Send, E: Send> {
-    async fn and_then_async<U, F, Fut>(self, f: F) -> std::result::Result<U, E>
-    where
-        F: FnOnce(T) -> Fut + Send,
-        Fut: Future<Output = Result<U, E>> + Send,
-        Self: Sized;
+impl RequestError {
+    /// This method reads an unsuccessful response to a request sent to the
+    /// given endpoint into an error. The endpoint is passed in, as responses
+    /// received over a unix socket do not carry the URL of their request.
+    pub(crate) async fn from_response(
+        method: Method,
+        endpoint: String,
+        response: HttpResponse,
+    ) -> ConsulError {
+        let status = response.status();
+        // the body is only informative, so failing to read it is not an error
+        let body = response.text().await.unwrap_or_default();
+        RequestError { method, endpoint, status, body }.into()
+    }
+}
+
+impl From<RequestError> for ConsulError {
+    fn from(err: RequestError) -> Self {
+        match err.status {
+            StatusCode::FORBIDDEN => ConsulError::PermissionDenied(err),
+            StatusCode::NOT_FOUND => ConsulError::NotFound(err),
+            StatusCode::TOO_MANY_REQUESTS => ConsulError::RateLimited(err),
+            status if status.is_server_error() && err.body.contains("No cluster leader") => {
+                ConsulError::LeaderUnavailable(err)
+            }
+            // write requests are rejected with a 503 by the servers' rate limits
+            StatusCode::SERVICE_UNAVAILABLE if err.body.contains("rate limit exceeded") => {
+                ConsulError::RateLimited(err)
+            }
+            _ => ConsulError::RequestFailed(err),
+        }
+    }
 }
 
-#[async_trait]
-impl<T: Send, E: Send> AndThenAsync<T, E> for std::result::Result<T, E> {
-    async fn and_then_async<U, F, Fut>(self, f: F) -> std::result::Result<U, E>
-    where
-        F: FnOnce(T) -> Fut + Send,
-        Fut: Future<Output = Result<U, E>> + Send,
-        Self: Sized,
-    {
-        match self {
-            Ok(inner) => Ok(f(inner).await?),
-            Err(e) => Err(e),
+impl QueryMeta {
+    /// This method extracts the query metadata from the headers of a response.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        QueryMeta {
+            last_index: header("X-Consul-Index").and_then(|v| v.parse().ok()),
+            known_leader: header("X-Consul-KnownLeader") == Some("true"),
+            last_contact: header("X-Consul-LastContact")
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_millis),
+            cache_hit: header("X-Cache").map(|v| v.eq_ignore_ascii_case("HIT")),
+            cache_age: header("Age").and_then(|v| v.parse().ok()).map(Duration::from_secs),
         }
     }
 }
 
 impl Client {
-    #[tracing::instrument]
-    pub(crate) async fn send_with_empty<
-        Path: AsRef<str> + Debug,
-        Body: Serialize + Debug,
-        Response: DeserializeOwned,
-    >(
+    /// This method creates a request builder for the given path, with the
+    /// parameters, body and query options applied.
+    fn build_request<Path: AsRef<str>, Body: Serialize>(
         &self,
         method: Method,
         path: Path,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         body: Option<Body>,
         options: Option<QueryOptions>,
-    ) -> ConsulResult<Option<Response>> {
+    ) -> ConsulResult<RequestBuilder> {
         // unwrap parameters
         let mut params = params.unwrap_or_default();
+        let options = options.unwrap_or_default();
         // if datacenter option is specified, set
         let datacenter: Option<String> =
-            options.and_then(|o| o.datacenter).or_else(|| self.config.datacenter.as_ref().cloned());
+            options.datacenter.or_else(|| self.config.datacenter.as_ref().cloned());
         if let Some(dc) = datacenter {
-            params.insert(String::from("dc"), dc);
+            params.push((String::from("dc"), dc));
+        }
+        // blocking query parameters
+        if let Some(index) = options.wait_index {
+            params.push((String::from("index"), index.to_string()));
+        }
+        if let Some(wait) = options.wait_time {
+            params.push((String::from("wait"), format!("{}ms", wait.as_millis())));
+        }
+        // filtering and sorting parameters
+        if let Some(near) = options.near {
+            params.push((String::from("near"), near));
+        }
+        for (key, value) in options.node_meta {
+            params.push((String::from("node-meta"), format!("{}:{}", key, value)));
+        }
+        if let Some(filter) = options.filter {
+            params.push((String::from("filter"), filter));
+        }
+        // consistency parameters
+        let consistent = options.consistency == ConsistencyMode::Consistent;
+        match options.consistency {
+            ConsistencyMode::Default => {}
+            ConsistencyMode::Consistent => params.push((String::from("consistent"), String::new())),
+            ConsistencyMode::Stale => params.push((String::from("stale"), String::new())),
+        }
+        // consistent queries are always served by the leader, and the API
+        // rejects them if a staleness bound is given
+        if let Some(max_stale) = options.max_stale.filter(|_| !consistent) {
+            params.push((String::from("max_stale"), format!("{}ms", max_stale.as_millis())));
+        }
+        // caching parameters
+        let mut cache_control = Vec::new();
+        if options.cached {
+            params.push((String::from("cached"), String::new()));
+            if let Some(max_age) = options.max_age {
+                cache_control.push(format!("max-age={}", max_age.as_secs()));
+            }
+            if let Some(stale_if_error) = options.stale_if_error {
+                cache_control.push(format!("stale-if-error={}", stale_if_error.as_secs()));
+            }
+        }
+        // tenancy parameters
+        #[cfg(feature = "enterprise")]
+        if let Some(namespace) = options.namespace.or_else(|| self.config.namespace.clone()) {
+            params.push((String::from("ns"), namespace));
+        }
+        #[cfg(feature = "enterprise")]
+        if let Some(partition) = options.partition.or_else(|| self.config.partition.clone()) {
+            params.push((String::from("partition"), partition));
+        }
+        if let Some(peer) = options.peer {
+            params.push((String::from("peer"), peer));
         }
         // parse url and create builder
-        let url = Url::parse_with_params(
-            &format!("{}{}", self.config.address, path.as_ref()),
-            params.iter(),
-        )
-        .unwrap();
+        let url = self.build_url(path.as_ref(), &params)?;
         let builder = self.config.http_client.request(method, url);
         // add body if specified
         let builder = if let Some(b) = body { builder.json(&b) } else { builder };
-        // add query options
-        let builder = match &self.config.token {
+        let builder = if cache_control.is_empty() {
+            builder
+        } else {
+            builder.header("Cache-Control", cache_control.join(", "))
+        };
+        let builder = match &self.config.basic_auth {
+            Some(auth) => builder.basic_auth(&auth.username, auth.password.as_ref()),
+            None => builder,
+        };
+        // the token of the request overrides the token of the client
+        Ok(match options.token.as_ref().or(self.config.token.as_ref()) {
             Some(val) => builder.header("X-Consul-Token", val),
             None => builder,
+        })
+    }
+
+    /// This method builds the URL of a request to the given path, percent
+    /// encoding each of its segments. Paths containing `.` or `..` segments,
+    /// such as keys like `a/../b`, are rejected, as they would be resolved to a
+    /// different path.
+    fn build_url(&self, path: &str, params: &[(String, String)]) -> ConsulResult<Url> {
+        let invalid = |source| ConsulError::InvalidUrl {
+            url: format!("{}{}", self.config.address, path),
+            source,
         };
+        let segments = path.trim_start_matches('/').split('/');
+        if segments.clone().any(|segment| segment == "." || segment == "..") {
+            return Err(invalid(None));
+        }
+        #[cfg(unix)]
+        let base = match self.socket {
+            Some(_) => crate::unix::UNIX_BASE_URL,
+            None => &self.config.address,
+        };
+        #[cfg(not(unix))]
+        let base = &self.config.address;
+        let mut url = Url::parse(base).map_err(|err| invalid(Some(err)))?;
+        url.path_segments_mut()
+            .map_err(|_| invalid(Some(url::ParseError::RelativeUrlWithCannotBeABaseBase)))?
+            .pop_if_empty()
+            .extend(segments);
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        Ok(url)
+    }
+
+    /// This method sends a request to the Consul API, returning the raw
+    /// response.
+    pub(crate) async fn execute<Path: AsRef<str>, Body: Serialize>(
+        &self,
+        method: Method,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        body: Option<Body>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<HttpResponse> {
+        let builder = self.build_request(method, path, params, body, options)?;
+        self.dispatch(builder).await
+    }
+
+    /// This method sends a built request, over the agent's unix socket if it
+    /// has one.
+    async fn dispatch(&self, builder: RequestBuilder) -> ConsulResult<HttpResponse> {
+        #[cfg(unix)]
+        if let Some(socket) = &self.socket {
+            return socket.send(builder.build()?).await;
+        }
+        Ok(builder.send().await?)
+    }
+
+    /// This method sends a request to the Consul API, with the response
+    /// potentially being empty. The [QueryMeta] of the response is returned
+    /// alongside the payload.
+    #[cfg(any(feature = "acl", feature = "agent", feature = "catalog", feature = "kv"))]
+    #[tracing::instrument]
+    pub(crate) async fn send_with_empty_meta<
+        Path: AsRef<str> + Debug,
+        Body: Serialize + Debug,
+        Response: DeserializeOwned,
+    >(
+        &self,
+        method: Method,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        body: Option<Body>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<Response>, QueryMeta)> {
         // send request
-        let response = builder.send().await?;
+        let endpoint = path.as_ref().to_owned();
+        let response = self.execute(method.clone(), path, params, body, options).await?;
+        let meta = QueryMeta::from_headers(response.headers());
         if response.status() == StatusCode::NOT_FOUND {
-            return Ok(None);
+            return Ok((None, meta));
         }
         if !response.status().is_success() {
-            return Err(ConsulError::RequestFailed(response.status()));
+            return Err(RequestError::from_response(method, endpoint, response).await);
         }
         let response = response.text().await?;
         if response.is_empty() {
-            return Ok(None);
+            return Ok((None, meta));
         }
         let response = serde_json::from_str(&response).map_err(ConsulError::DecodeError)?;
-        Ok(response)
+        Ok((response, meta))
+    }
+
+    /// This method sends a request to the Consul API, with the response
+    /// potentially being empty.
+    #[cfg(any(feature = "acl", feature = "agent"))]
+    #[tracing::instrument]
+    pub(crate) async fn send_with_empty<
+        Path: AsRef<str> + Debug,
+        Body: Serialize + Debug,
+        Response: DeserializeOwned,
+    >(
+        &self,
+        method: Method,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        body: Option<Body>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Option<Response>> {
+        self.send_with_empty_meta(method, path, params, body, options).await.map(|(r, _)| r)
+    }
+
+    /// This method sends a request to the Consul API, returning the [QueryMeta]
+    /// of the response alongside the payload.
+    ///
+    /// See [Client::send] for more information.
+    #[tracing::instrument]
+    pub(crate) async fn send_with_meta<
+        Path: AsRef<str> + Debug,
+        Body: Serialize + Debug,
+        Response: DeserializeOwned,
+    >(
+        &self,
+        method: Method,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        body: Option<Body>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Response, QueryMeta)> {
+        let endpoint = path.as_ref().to_owned();
+        let response = self.execute(method.clone(), path, params, body, options).await?;
+        if !response.status().is_success() {
+            return Err(RequestError::from_response(method, endpoint, response).await);
+        }
+        let meta = QueryMeta::from_headers(response.headers());
+        let response = response.json::<Response>().await?;
+        Ok((response, meta))
     }
 
     /// This method sends a request to the Consul API.
@@ -89,8 +293,9 @@ impl Client {
     /// provided method. If params exists, the request will be sent with the
     /// given parameters, plus any defined in the client options.
     ///
-    /// This method will error if the request fails, and will panic if the
-    /// URL or parameters are invalid.
+    /// This method will error if the request fails or is rejected, or with
+    /// [ConsulError::InvalidUrl] if the URL built from the path and
+    /// parameters is invalid.
     #[tracing::instrument]
     pub(crate) async fn send<
         Path: AsRef<str> + Debug,
@@ -100,51 +305,80 @@ impl Client {
         &self,
         method: Method,
         path: Path,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         body: Option<Body>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Response> {
-        // unwrap parameters
-        let mut params = params.unwrap_or_default();
-        // if datacenter option is specified, set
-        let datacenter: Option<String> =
-            options.and_then(|o| o.datacenter).or_else(|| self.config.datacenter.as_ref().cloned());
-        if let Some(dc) = datacenter {
-            params.insert(String::from("dc"), dc);
-        }
-        // parse url and create builder
-        let url = Url::parse_with_params(
-            &format!("{}{}", self.config.address, path.as_ref()),
-            params.iter(),
-        )
-        .unwrap();
-        let builder = self.config.http_client.request(method, url);
-        // add body if specified
-        let builder = if let Some(b) = body { builder.json(&b) } else { builder };
-        // add query options
-        let builder = match &self.config.token {
-            Some(val) => builder.header("X-Consul-Token", val),
-            None => builder,
-        };
-        builder
-            .send()
-            .await
-            .and_then_async(|x| async { x.json::<Response>().await })
+        self.send_with_meta(method, path, params, body, options).await.map(|(r, _)| r)
+    }
+
+    /// This method makes a GET request with query parameters to the given
+    /// path, returning the [QueryMeta] of the response alongside the payload.
+    #[cfg(any(feature = "catalog", feature = "connect", feature = "health", feature = "session"))]
+    #[tracing::instrument]
+    pub(crate) async fn get_with_meta<Path: AsRef<str> + Debug, T: DeserializeOwned>(
+        &self,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(T, QueryMeta)> {
+        self.send_with_meta::<Path, (), T>(Method::GET, path, params, None, options).await
+    }
+
+    /// This method makes a GET request with query parameters to the given
+    /// path, with the response potentially being empty. The [QueryMeta] of the
+    /// response is returned alongside the payload.
+    #[cfg(any(feature = "catalog", feature = "kv"))]
+    #[tracing::instrument]
+    pub(crate) async fn get_with_empty_meta<
+        Path: AsRef<str> + Debug,
+        Response: DeserializeOwned,
+    >(
+        &self,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Option<Response>, QueryMeta)> {
+        self.send_with_empty_meta::<Path, (), Response>(Method::GET, path, params, None, options)
             .await
-            .map_err(ConsulError::HttpError)
     }
+
+    /// This method makes a GET request with query parameters to the given
+    /// path, returning the raw response body, or `None` if the resource was
+    /// not found.
+    #[cfg(feature = "kv")]
+    #[tracing::instrument]
+    pub(crate) async fn get_raw<Path: AsRef<str> + Debug>(
+        &self,
+        path: Path,
+        params: Option<Vec<(String, String)>>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Option<Vec<u8>>> {
+        let endpoint = path.as_ref().to_owned();
+        let response = self.execute::<Path, ()>(Method::GET, path, params, None, options).await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(RequestError::from_response(Method::GET, endpoint, response).await);
+        }
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
     /// This method makes a GET request with query parameters to the given path.
+    #[cfg(any(feature = "acl", feature = "agent", feature = "catalog"))]
     #[tracing::instrument]
     pub(crate) async fn get_with_params<Path: AsRef<str> + Debug, T: DeserializeOwned>(
         &self,
         path: Path,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<T> {
         self.send::<Path, (), T>(Method::GET, path, params, None, options).await
     }
 
     /// This method makes a GET request to the given path.
+    #[cfg(any(feature = "acl", feature = "agent", feature = "catalog"))]
     #[tracing::instrument]
     pub(crate) async fn get<Path: AsRef<str> + Debug, T: DeserializeOwned>(
         &self,
@@ -154,19 +388,8 @@ impl Client {
         self.get_with_params(path, None, options).await
     }
 
-    /// This method makes a GET request to the given path, with the response
-    /// potentially being empty.
-    #[tracing::instrument]
-    pub(crate) async fn get_with_empty<Path: AsRef<str> + Debug, Response: DeserializeOwned>(
-        &self,
-        path: Path,
-        params: Option<HashMap<String, String>>,
-        options: Option<QueryOptions>,
-    ) -> ConsulResult<Option<Response>> {
-        self.send_with_empty::<Path, (), Response>(Method::GET, path, params, None, options).await
-    }
-
     /// This method makes a POST request to the given path.
+    #[cfg(feature = "acl")]
     #[tracing::instrument]
     pub(crate) async fn post<
         Path: AsRef<str> + Debug,
@@ -176,7 +399,7 @@ impl Client {
         &self,
         path: Path,
         body: Body,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Response> {
         self.send::<Path, Body, Response>(Method::POST, path, params, Some(body), options).await
@@ -184,6 +407,7 @@ impl Client {
 
     /// This method makes a POST request to the given path, with the response
     /// potentially being empty.
+    #[cfg(feature = "acl")]
     #[tracing::instrument]
     pub(crate) async fn post_with_empty<
         Path: AsRef<str> + Debug,
@@ -193,7 +417,7 @@ impl Client {
         &self,
         path: Path,
         body: Body,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Option<Response>> {
         self.send_with_empty::<Path, Body, Response>(
@@ -207,6 +431,13 @@ impl Client {
     }
 
     /// This method makes a PUT request to the given path.
+    #[cfg(any(
+        feature = "acl",
+        feature = "agent",
+        feature = "catalog",
+        feature = "connect",
+        feature = "session"
+    ))]
     #[tracing::instrument]
     pub(crate) async fn put<
         Path: AsRef<str> + Debug,
@@ -216,14 +447,35 @@ impl Client {
         &self,
         path: Path,
         body: Body,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Response> {
         self.send::<Path, Body, Response>(Method::PUT, path, params, Some(body), options).await
     }
 
+    /// This method makes a PUT request to the given path, sending the given
+    /// bytes as the raw request body.
+    #[cfg(feature = "kv")]
+    #[tracing::instrument(skip(body))]
+    pub(crate) async fn put_raw<Path: AsRef<str> + Debug, Response: DeserializeOwned>(
+        &self,
+        path: Path,
+        body: Vec<u8>,
+        params: Option<Vec<(String, String)>>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<Response> {
+        let endpoint = path.as_ref().to_owned();
+        let builder = self.build_request::<Path, ()>(Method::PUT, path, params, None, options)?;
+        let response = self.dispatch(builder.body(body)).await?;
+        if !response.status().is_success() {
+            return Err(RequestError::from_response(Method::PUT, endpoint, response).await);
+        }
+        Ok(response.json().await?)
+    }
+
     /// This method makes a PUT request to the given path, with the response
     /// potentially being empty.
+    #[cfg(feature = "agent")]
     pub(crate) async fn put_with_empty<
         Path: AsRef<str> + Debug,
         Body: Serialize + Debug,
@@ -232,7 +484,7 @@ impl Client {
         &self,
         path: Path,
         body: Body,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Option<Response>> {
         self.send_with_empty::<Path, Body, Response>(Method::PUT, path, params, Some(body), options)
@@ -240,13 +492,219 @@ impl Client {
     }
 
     /// This method makes a DELETE request to the given path.
+    #[cfg(any(feature = "acl", feature = "kv"))]
     #[tracing::instrument]
     pub(crate) async fn delete<Path: AsRef<str> + Debug, Response: DeserializeOwned>(
         &self,
         path: Path,
-        params: Option<HashMap<String, String>>,
+        params: Option<Vec<(String, String)>>,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Response> {
         self.send::<Path, (), Response>(Method::DELETE, path, params, None, options).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{
+        header::{HeaderMap, HeaderValue},
+        Method,
+    };
+
+    use crate::{
+        Client, Config, ConsistencyMode, ConsulError, QueryMeta, QueryOptions, RequestError,
+    };
+
+    #[test]
+    fn test_query_meta_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Consul-Index", HeaderValue::from_static("42"));
+        headers.insert("X-Consul-KnownLeader", HeaderValue::from_static("true"));
+        headers.insert("X-Consul-LastContact", HeaderValue::from_static("150"));
+        headers.insert("X-Cache", HeaderValue::from_static("HIT"));
+        headers.insert("Age", HeaderValue::from_static("3"));
+        let meta = QueryMeta::from_headers(&headers);
+        assert_eq!(meta.last_index, Some(42));
+        assert!(meta.known_leader);
+        assert_eq!(meta.last_contact, Some(Duration::from_millis(150)));
+        assert_eq!(meta.cache_hit, Some(true));
+        assert_eq!(meta.cache_age, Some(Duration::from_secs(3)));
+
+        let meta = QueryMeta::from_headers(&HeaderMap::new());
+        assert_eq!(meta, QueryMeta::default());
+    }
+
+    #[test]
+    fn test_build_request_query_options() {
+        let client = Client::new(Config::new().unwrap());
+        let mut options = QueryOptions {
+            near: Some(String::from("_agent")),
+            filter: Some(String::from("Service.Port == 80")),
+            ..Default::default()
+        };
+        options.node_meta.insert(String::from("rack"), String::from("a"));
+        let params = vec![
+            (String::from("tag"), String::from("a")),
+            (String::from("tag"), String::from("b")),
+        ];
+        let request = client
+            .build_request::<_, ()>(
+                Method::GET,
+                "/v1/health/service/web",
+                Some(params),
+                None,
+                Some(options),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        let pairs: Vec<(String, String)> = request.url().query_pairs().into_owned().collect();
+        let expected = [
+            ("tag", "a"),
+            ("tag", "b"),
+            ("near", "_agent"),
+            ("node-meta", "rack:a"),
+            ("filter", "Service.Port == 80"),
+        ];
+        assert_eq!(
+            pairs,
+            expected.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_build_request_consistency_and_cache() {
+        let client = Client::new(Config::new().unwrap());
+        let options = QueryOptions {
+            consistency: ConsistencyMode::Stale,
+            max_stale: Some(Duration::from_secs(5)),
+            cached: true,
+            max_age: Some(Duration::from_secs(30)),
+            stale_if_error: Some(Duration::from_secs(60)),
+            peer: Some(String::from("cluster-02")),
+            ..Default::default()
+        };
+        let request = client
+            .build_request::<_, ()>(Method::GET, "/v1/catalog/services", None, None, Some(options))
+            .unwrap()
+            .build()
+            .unwrap();
+        let pairs: Vec<(String, String)> = request.url().query_pairs().into_owned().collect();
+        let expected =
+            [("stale", ""), ("max_stale", "5000ms"), ("cached", ""), ("peer", "cluster-02")];
+        assert_eq!(
+            pairs,
+            expected.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>()
+        );
+        assert_eq!(request.headers()["Cache-Control"], "max-age=30, stale-if-error=60");
+
+        // cache directives are only sent for cached queries
+        let options = QueryOptions {
+            consistency: ConsistencyMode::Consistent,
+            max_age: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        let request = client
+            .build_request::<_, ()>(Method::GET, "/v1/catalog/services", None, None, Some(options))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.url().query(), Some("consistent="));
+        assert!(request.headers().get("Cache-Control").is_none());
+
+        // the staleness bound does not apply to consistent queries
+        let options = QueryOptions {
+            consistency: ConsistencyMode::Consistent,
+            max_stale: Some(Duration::from_secs(5)),
+            ..Default::default()
+        };
+        let request = client
+            .build_request::<_, ()>(Method::GET, "/v1/catalog/services", None, None, Some(options))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.url().query(), Some("consistent="));
+    }
+
+    #[test]
+    fn test_request_error_variants() {
+        let error = |status: u16, body: &str| -> ConsulError {
+            RequestError {
+                method: Method::PUT,
+                endpoint: String::from("/v1/kv/a"),
+                status: status.try_into().unwrap(),
+                body: body.to_owned(),
+            }
+            .into()
+        };
+        assert!(matches!(error(403, "Permission denied"), ConsulError::PermissionDenied(_)));
+        assert!(matches!(error(404, ""), ConsulError::NotFound(_)));
+        assert!(matches!(error(429, "rate limit exceeded"), ConsulError::RateLimited(_)));
+        assert!(matches!(error(503, "rate limit exceeded"), ConsulError::RateLimited(_)));
+        assert!(matches!(
+            error(500, "rpc error making call: No cluster leader"),
+            ConsulError::LeaderUnavailable(_)
+        ));
+        assert!(matches!(error(400, "Invalid check"), ConsulError::RequestFailed(_)));
+
+        let err = error(400, "Invalid check\n");
+        assert_eq!(err.status(), Some(400.try_into().unwrap()));
+        assert_eq!(
+            err.to_string(),
+            "request failed: PUT /v1/kv/a returned 400 Bad Request: Invalid check"
+        );
+    }
+
+    #[test]
+    fn test_build_url_encodes_path() {
+        let client =
+            Client::new(Config::builder().address("localhost:8500/consul/").build().unwrap());
+        let url = client.build_url("/v1/kv/a b/c?d#e%f", &[]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8500/consul/v1/kv/a%20b/c%3Fd%23e%25f");
+        let url = client.build_url("/v1/kv/", &[(String::from("recurse"), String::new())]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8500/consul/v1/kv/?recurse=");
+        for path in ["/v1/kv/a/../b", "/v1/kv/./a", "/v1/kv/a/.."] {
+            assert!(matches!(client.build_url(path, &[]), Err(ConsulError::InvalidUrl { .. })));
+        }
+        let url = client.build_url("/v1/kv/a/.../b.c", &[]).unwrap();
+        assert_eq!(url.as_str(), "http://localhost:8500/consul/v1/kv/a/.../b.c");
+
+        let client =
+            Client::new(Config { address: String::from("not a url"), ..Config::new().unwrap() });
+        assert!(matches!(client.build_url("/v1/kv/a", &[]), Err(ConsulError::InvalidUrl { .. })));
+    }
+
+    #[test]
+    fn test_build_request_config_defaults() {
+        let builder = Config::builder().basic_auth("user", Some("pass"));
+        #[cfg(feature = "enterprise")]
+        let builder = builder.namespace("team");
+        let client = Client::new(builder.build().unwrap());
+        let request = client
+            .build_request::<_, ()>(Method::GET, "/v1/kv/a", None, None, None)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()["Authorization"], "Basic dXNlcjpwYXNz");
+        #[cfg(feature = "enterprise")]
+        assert_eq!(request.url().query(), Some("ns=team"));
+    }
+
+    #[test]
+    fn test_build_request_token() {
+        let client = Client::new(Config::builder().token("client").build().unwrap());
+        let token = |options| {
+            let request = client
+                .build_request::<_, ()>(Method::GET, "/v1/kv/a", None, None, options)
+                .unwrap()
+                .build()
+                .unwrap();
+            request.headers()["X-Consul-Token"].to_str().unwrap().to_owned()
+        };
+        assert_eq!(token(None), "client");
+        let options = QueryOptions { token: Some(String::from("tenant")), ..Default::default() };
+        assert_eq!(token(Some(options)), "tenant");
+    }
+}
@@ -0,0 +1,408 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    sync::watch::{channel, Receiver},
+    task::JoinHandle,
+};
+
+use crate::{
+    lock::{create_renewed_session, spawn_monitor},
+    watch::next_index,
+    Client, ConsulError, ConsulResult, KVPair, QueryOptions, Session, SessionEntry, KV,
+};
+
+/// The flags value set on keys used by a [Semaphore]. This is used to detect
+/// keys which are being used by a different recipe, such as a lock.
+pub const SEMAPHORE_FLAG_VALUE: u64 = 0xe0f69a2baa414de0;
+
+/// The name of the key, within the semaphore's prefix, holding the set of
+/// current holders.
+const SEMAPHORE_LOCK_KEY: &str = ".lock";
+
+/// Options used to acquire a [Semaphore].
+#[derive(Clone, Debug)]
+pub struct SemaphoreOptions {
+    /// The prefix under which the semaphore's keys are stored.
+    pub prefix: String,
+    /// The maximum number of holders of the semaphore. All contenders must
+    /// agree on this limit.
+    pub limit: u32,
+    /// The value stored in this contender's key.
+    pub value: Vec<u8>,
+    /// The ID of an existing session to contend with. If `None`, a session is
+    /// created from `session_entry`, renewed in the background while the
+    /// semaphore is held, and destroyed when it is released.
+    pub session: Option<String>,
+    /// The template used to create a session when `session` is `None`. If no
+    /// TTL is set, a TTL of 15 seconds is used.
+    pub session_entry: SessionEntry,
+    /// The maximum time to wait on each blocking query while waiting for a
+    /// slot.
+    pub wait_time: Duration,
+    /// If set, a slot is only waited on for up to `wait_time`, after which
+    /// [Semaphore::acquire] gives up.
+    pub try_once: bool,
+    /// The number of consecutive failed queries tolerated while monitoring a
+    /// held slot, before it is considered lost.
+    pub monitor_retries: u32,
+    /// The query options used for requests made by the semaphore.
+    pub query_options: QueryOptions,
+}
+
+impl Default for SemaphoreOptions {
+    fn default() -> Self {
+        SemaphoreOptions {
+            prefix: String::new(),
+            limit: 1,
+            value: Vec::new(),
+            session: None,
+            session_entry: SessionEntry {
+                name: Some(String::from("Consul API Semaphore")),
+                behavior: Some(String::from("delete")),
+                ..Default::default()
+            },
+            wait_time: Duration::from_secs(15),
+            try_once: false,
+            monitor_retries: 0,
+            query_options: QueryOptions::default(),
+        }
+    }
+}
+
+/// The contents of the semaphore's `.lock` key.
+#[derive(Default, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "PascalCase")]
+struct SemaphoreLock {
+    /// The maximum number of holders.
+    limit: u32,
+    /// The sessions currently holding the semaphore.
+    holders: HashMap<String, bool>,
+}
+
+/// A slot held in a distributed semaphore.
+///
+/// This implements Consul's [semaphore] recipe: each contender creates a key
+/// under the prefix with its session, and the set of holders is stored in a
+/// `.lock` key which is updated using check-and-set. Holders whose sessions
+/// have been invalidated are pruned by other contenders.
+///
+/// Like [crate::Lock], the session is renewed and the slot is monitored in
+/// background tasks, so a semaphore must be acquired from within a Tokio
+/// runtime.
+///
+/// [semaphore]: https://learn.hashicorp.com/tutorials/consul/distributed-semaphore
+#[derive(Debug)]
+pub struct Semaphore {
+    client: Client,
+    prefix: String,
+    session: String,
+    owns_session: bool,
+    query_options: QueryOptions,
+    lost: Receiver<bool>,
+    monitor: JoinHandle<()>,
+    renewal: Option<JoinHandle<()>>,
+    released: bool,
+}
+
+impl Semaphore {
+    /// This method waits until a slot in the semaphore described by the given
+    /// options is acquired.
+    ///
+    /// If `try_once` is set and no slot could be acquired within `wait_time`,
+    /// `None` is returned. An error is returned if the prefix is in use by
+    /// something other than a semaphore, or if the semaphore's limit does not
+    /// match the limit of existing holders.
+    pub async fn acquire(
+        client: &Client,
+        options: SemaphoreOptions,
+    ) -> ConsulResult<Option<Semaphore>> {
+        if options.prefix.is_empty() {
+            return Err(ConsulError::EmptyKey);
+        }
+        if options.limit == 0 {
+            return Err(ConsulError::MissingParameter(String::from("limit")));
+        }
+        let prefix = format!("{}/", options.prefix.trim_end_matches('/'));
+        let (lost_tx, lost) = channel(false);
+        let lost_tx = Arc::new(lost_tx);
+        // create a session if none was provided
+        let (session, renewal) = match &options.session {
+            Some(session) => (session.clone(), None),
+            None => {
+                let (session, renewal) = create_renewed_session(
+                    client,
+                    &options.session_entry,
+                    &options.query_options,
+                    lost_tx.clone(),
+                )
+                .await?;
+                (session, Some(renewal))
+            }
+        };
+        match wait_for_slot(client, &prefix, &session, &options).await {
+            Ok(true) => {}
+            result => {
+                // give up the contender key if it was created
+                let _ = client
+                    .delete_entry(
+                        &contender_key(&prefix, &session),
+                        Some(options.query_options.clone()),
+                    )
+                    .await;
+                if let Some(renewal) = renewal {
+                    renewal.abort();
+                    let _ =
+                        client.destroy_session(&session, Some(options.query_options.clone())).await;
+                }
+                return result.map(|_| None);
+            }
+        }
+        let held_session = session.clone();
+        let lock_key = lock_key(&prefix);
+        let monitor = spawn_monitor(
+            client,
+            &prefix,
+            options.monitor_retries,
+            &options.query_options,
+            lost_tx,
+            move |entries| {
+                entries
+                    .iter()
+                    .find(|entry| entry.key == lock_key)
+                    .and_then(|entry| decode_lock(entry).ok())
+                    .is_some_and(|lock| lock.holders.contains_key(&held_session))
+            },
+        );
+        Ok(Some(Semaphore {
+            client: client.clone(),
+            prefix,
+            session,
+            owns_session: renewal.is_some(),
+            query_options: options.query_options,
+            lost,
+            monitor,
+            renewal,
+            released: false,
+        }))
+    }
+
+    /// This method returns the ID of the session holding the slot.
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    /// This method returns `true` if the slot has been lost, either because the
+    /// session was invalidated or it was removed from the set of holders.
+    pub fn is_lost(&self) -> bool {
+        *self.lost.borrow()
+    }
+
+    /// This method returns a future which resolves once the slot is lost.
+    pub fn lost(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut lost = self.lost.clone();
+        async move {
+            // an error means the slot was released, so it is no longer held
+            let _ = lost.wait_for(|lost| *lost).await;
+        }
+    }
+
+    /// This method releases the slot, and destroys its session if it was
+    /// created by the semaphore.
+    pub async fn release(mut self) -> ConsulResult<()> {
+        self.released = true;
+        self.monitor.abort();
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+        release(&self.client, &self.prefix, &self.session, self.owns_session, &self.query_options)
+            .await
+    }
+}
+
+impl Drop for Semaphore {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        self.monitor.abort();
+        if let Some(renewal) = self.renewal.take() {
+            renewal.abort();
+        }
+        // release the slot in the background if a runtime is still available
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            let client = self.client.clone();
+            let prefix = self.prefix.clone();
+            let session = self.session.clone();
+            let owns_session = self.owns_session;
+            let options = self.query_options.clone();
+            handle.spawn(async move {
+                if let Err(err) = release(&client, &prefix, &session, owns_session, &options).await
+                {
+                    tracing::warn!("failed to release semaphore on {}: {}", prefix, err);
+                }
+            });
+        }
+    }
+}
+
+/// This function creates the contender key for the given session, and waits
+/// until the session is added to the set of holders. Returns `false` if the
+/// semaphore is in `try_once` mode and the wait time elapsed.
+async fn wait_for_slot(
+    client: &Client,
+    prefix: &str,
+    session: &str,
+    options: &SemaphoreOptions,
+) -> ConsulResult<bool> {
+    let contender = KVPair {
+        key: contender_key(prefix, session),
+        value: options.value.clone(),
+        flags: Some(SEMAPHORE_FLAG_VALUE),
+        session: Some(session.to_owned()),
+        ..Default::default()
+    };
+    if !client.acquire_entry(&contender, Some(options.query_options.clone())).await? {
+        return Err(ConsulError::AcquireFailed(contender.key));
+    }
+    let lock_key = lock_key(prefix);
+    let deadline = Instant::now() + options.wait_time;
+    let mut index = 0;
+    let mut attempted = false;
+    loop {
+        let mut query = options.query_options.clone();
+        query.wait_time = Some(options.wait_time);
+        if options.try_once && attempted {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+            query.wait_time = Some(remaining);
+        }
+        query.wait_index = if index > 0 { Some(index) } else { None };
+        let (entries, meta) = client.list_entries_with_meta(prefix, Some(query)).await?;
+        index = next_index(index, meta.last_index.unwrap_or(0));
+        attempted = true;
+
+        let mut lock_pair = None;
+        let mut live = HashSet::new();
+        for entry in entries {
+            if entry.flags != Some(SEMAPHORE_FLAG_VALUE) {
+                return Err(ConsulError::SemaphoreConflict(entry.key));
+            }
+            if entry.key == lock_key {
+                lock_pair = Some(entry);
+            } else if let Some(holder) = entry.session {
+                live.insert(holder);
+            } else {
+                // the contender's session was invalidated without deleting its key
+                client.delete_entry(&entry.key, Some(options.query_options.clone())).await?;
+            }
+        }
+
+        let (mut lock, cas_index) = match &lock_pair {
+            Some(pair) => (decode_lock(pair)?, pair.modifyindex.unwrap_or_default()),
+            None => (SemaphoreLock { limit: options.limit, holders: HashMap::new() }, 0),
+        };
+        if lock.limit != options.limit {
+            return Err(ConsulError::SemaphoreLimitConflict {
+                limit: options.limit,
+                existing: lock.limit,
+            });
+        }
+        // prune holders whose sessions are no longer alive
+        lock.holders.retain(|holder, _| live.contains(holder));
+        if lock.holders.contains_key(session) {
+            return Ok(true);
+        }
+        if lock.holders.len() >= lock.limit as usize {
+            continue;
+        }
+        lock.holders.insert(session.to_owned(), true);
+        let pair = encode_lock(&lock_key, &lock)?;
+        if client.cas_entry(&pair, cas_index, Some(options.query_options.clone())).await? {
+            return Ok(true);
+        }
+        // lost the race to update the holders, so retry immediately
+        index = 0;
+    }
+}
+
+/// This function removes the given session from the semaphore's holders, and
+/// deletes its contender key.
+async fn release(
+    client: &Client,
+    prefix: &str,
+    session: &str,
+    owns_session: bool,
+    options: &QueryOptions,
+) -> ConsulResult<()> {
+    let lock_key = lock_key(prefix);
+    loop {
+        let entries = client.get_entry(&lock_key, Some(options.clone())).await?;
+        let pair = match entries.first() {
+            Some(pair) => pair,
+            None => break,
+        };
+        let mut lock = decode_lock(pair)?;
+        if lock.holders.remove(session).is_none() {
+            break;
+        }
+        let update = encode_lock(&lock_key, &lock)?;
+        let cas_index = pair.modifyindex.unwrap_or_default();
+        if client.cas_entry(&update, cas_index, Some(options.clone())).await? {
+            break;
+        }
+    }
+    client.delete_entry(&contender_key(prefix, session), Some(options.clone())).await?;
+    if owns_session {
+        client.destroy_session(session, Some(options.clone())).await?;
+    }
+    Ok(())
+}
+
+/// This function returns the key of the given session's contender entry.
+fn contender_key(prefix: &str, session: &str) -> String {
+    format!("{}{}", prefix, session)
+}
+
+/// This function returns the key holding the set of holders.
+fn lock_key(prefix: &str) -> String {
+    format!("{}{}", prefix, SEMAPHORE_LOCK_KEY)
+}
+
+/// This function decodes the set of holders from the `.lock` key.
+fn decode_lock(pair: &KVPair) -> ConsulResult<SemaphoreLock> {
+    Ok(serde_json::from_slice(&pair.value)?)
+}
+
+/// This function encodes the set of holders into a pair for the `.lock` key.
+fn encode_lock(key: &str, lock: &SemaphoreLock) -> ConsulResult<KVPair> {
+    Ok(KVPair {
+        key: key.to_owned(),
+        value: serde_json::to_vec(lock)?,
+        flags: Some(SEMAPHORE_FLAG_VALUE),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_lock, encode_lock, SemaphoreLock, SEMAPHORE_FLAG_VALUE};
+
+    #[test]
+    fn test_lock_round_trip() {
+        let mut lock = SemaphoreLock { limit: 2, ..Default::default() };
+        lock.holders.insert(String::from("session"), true);
+        let pair = encode_lock("prefix/.lock", &lock).unwrap();
+        assert_eq!(pair.flags, Some(SEMAPHORE_FLAG_VALUE));
+
+        let decoded = decode_lock(&pair).unwrap();
+        assert_eq!(decoded.limit, 2);
+        assert!(decoded.holders.contains_key("session"));
+    }
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
 
-use crate::{sealed::Sealed, Client, ConsulResult, QueryOptions};
+use crate::{sealed::Sealed, Client, ConsulResult, QueryMeta, QueryOptions};
 
 /// A wrapper struct for session IDs.
 #[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize, Debug)]
@@ -14,17 +16,31 @@ pub struct SessionID {
 #[derive(Clone, Default, Eq, PartialEq, Serialize, Deserialize, Debug)]
 #[serde(default, rename_all = "PascalCase")]
 pub struct SessionEntry {
+    #[serde(rename = "CreateIndex")]
     pub createindex: Option<u64>,
     #[serde(rename = "ID")]
     pub id: Option<String>,
     pub name: Option<String>,
     pub node: Option<String>,
+    /// The lock delay of the session, in nanoseconds. Values below 1000 are
+    /// interpreted by Consul as seconds.
+    #[serde(rename = "LockDelay")]
     pub lockdelay: Option<u64>, //delay: Change this to a Durations
     pub behavior: Option<String>,
     pub checks: Option<Vec<String>>,
+    /// The TTL of the session, as a duration string such as `"15s"`.
+    #[serde(rename = "TTL")]
     pub ttl: Option<String>,
 }
 
+impl SessionEntry {
+    /// This method returns the TTL of the session as a [Duration], or `None` if
+    /// the session has no TTL or it could not be parsed.
+    pub fn ttl_duration(&self) -> Option<Duration> {
+        self.ttl.as_deref().and_then(parse_duration)
+    }
+}
+
 /// This trait provides methods for interacting with the agent's session store.
 #[async_trait]
 pub trait Session: Sealed {
@@ -64,6 +80,16 @@ pub trait Session: Sealed {
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<SessionEntry>>;
 
+    /// This method returns the requested session information, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [Session::get_session_info] for more information.
+    async fn get_session_info_with_meta(
+        &self,
+        id: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)>;
+
     /// This endpoint returns the list of active sessions.
     ///
     /// For more information, consult the relevant endpoint's [API
@@ -73,6 +99,15 @@ pub trait Session: Sealed {
     async fn list_sessions(&self, options: Option<QueryOptions>)
         -> ConsulResult<Vec<SessionEntry>>;
 
+    /// This endpoint returns the list of active sessions, along with the
+    /// [QueryMeta] of the response.
+    ///
+    /// See [Session::list_sessions] for more information.
+    async fn list_sessions_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)>;
+
     /// This method method returns the active sessions for a given node.
     ///
     /// For more information, consult the relevant endpoint's [API
@@ -85,6 +120,16 @@ pub trait Session: Sealed {
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<SessionEntry>>;
 
+    /// This method returns the active sessions for a given node, along with
+    /// the [QueryMeta] of the response.
+    ///
+    /// See [Session::list_session_for_node] for more information.
+    async fn list_session_for_node_with_meta(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)>;
+
     /// This method renews the given session. This should be used with sessions
     /// that have a TTL, and it extends the expiration by the TTL.
     ///
@@ -122,8 +167,17 @@ impl Session for Client {
         id: &str,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<SessionEntry>> {
+        self.get_session_info_with_meta(id, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn get_session_info_with_meta(
+        &self,
+        id: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)> {
         let path = format!("/v1/session/info/{}", id);
-        self.get(&path, options).await
+        self.get_with_meta(&path, None, options).await
     }
 
     #[tracing::instrument]
@@ -131,7 +185,15 @@ impl Session for Client {
         &self,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<SessionEntry>> {
-        self.get("/v1/session/list", options).await
+        self.list_sessions_with_meta(options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_sessions_with_meta(
+        &self,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)> {
+        self.get_with_meta("/v1/session/list", None, options).await
     }
 
     #[tracing::instrument]
@@ -140,8 +202,17 @@ impl Session for Client {
         node: &str,
         options: Option<QueryOptions>,
     ) -> ConsulResult<Vec<SessionEntry>> {
+        self.list_session_for_node_with_meta(node, options).await.map(|(r, _)| r)
+    }
+
+    #[tracing::instrument]
+    async fn list_session_for_node_with_meta(
+        &self,
+        node: &str,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<(Vec<SessionEntry>, QueryMeta)> {
         let path = format!("/v1/session/node/{}", node);
-        self.get(&path, options).await
+        self.get_with_meta(&path, None, options).await
     }
 
     #[tracing::instrument]
@@ -154,3 +225,53 @@ impl Session for Client {
         self.put(&path, None as Option<&()>, None, options).await
     }
 }
+
+/// This function parses a duration string in the format used by Consul, such
+/// as `"15s"`, `"1.5m"` or `"1h30m"`.
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest == "0" {
+        return Some(Duration::ZERO);
+    }
+    if rest.is_empty() {
+        return None;
+    }
+    let mut seconds = 0f64;
+    while !rest.is_empty() {
+        let number_end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len());
+        let scale = match &rest[..unit_end] {
+            "ns" => 1e-9,
+            "us" | "\u{b5}s" => 1e-6,
+            "ms" => 1e-3,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            _ => return None,
+        };
+        seconds += number * scale;
+        rest = &rest[unit_end..];
+    }
+    Some(Duration::from_secs_f64(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_duration;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0"), Some(Duration::ZERO));
+        assert_eq!(parse_duration("15s"), Some(Duration::from_secs(15)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5m"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("15"), None);
+        assert_eq!(parse_duration("15x"), None);
+    }
+}
@@ -0,0 +1,383 @@
+use async_trait::async_trait;
+use reqwest::{Method, StatusCode};
+
+#[cfg(feature = "agent")]
+use crate::AgentService;
+#[cfg(feature = "health")]
+use crate::HealthCheck;
+use crate::{
+    common::deserialize_null_default, sealed::Sealed, Client, ConsulError, ConsulResult, KVPair,
+    Node, QueryOptions, RequestError,
+};
+
+/// The maximum number of operations allowed in a single transaction.
+pub const MAX_TXN_OPS: usize = 64;
+
+/// The verb of a [KVTxnOp].
+///
+/// For more information, see the [API documentation].
+///
+/// [API documentation]: https://www.consul.io/api-docs/txn#tables-of-operations
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KVTxnVerb {
+    /// Sets the key to the given value.
+    Set,
+    /// Sets the key to the given value, if its modify index matches `index`.
+    Cas,
+    /// Locks the key with the given session.
+    Lock,
+    /// Unlocks the key with the given session.
+    Unlock,
+    /// Gets the key, failing if it does not exist.
+    Get,
+    /// Gets all keys with the given prefix.
+    GetTree,
+    /// Fails the transaction if the key's modify index does not match `index`.
+    CheckIndex,
+    /// Fails the transaction if the key is not locked by the given session.
+    CheckSession,
+    /// Fails the transaction if the key exists.
+    CheckNotExists,
+    /// Deletes the key.
+    Delete,
+    /// Deletes all keys with the given prefix.
+    DeleteTree,
+    /// Deletes the key, if its modify index matches `index`.
+    DeleteCas,
+}
+
+/// The verb of a node, service or check operation.
+///
+/// Check-and-set verbs use the modify index of the given entity.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TxnVerb {
+    /// Sets the entity.
+    Set,
+    /// Sets the entity, if its modify index matches.
+    Cas,
+    /// Gets the entity, failing if it does not exist.
+    Get,
+    /// Deletes the entity.
+    Delete,
+    /// Deletes the entity, if its modify index matches.
+    DeleteCas,
+}
+
+/// A KV operation within a transaction.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct KVTxnOp {
+    /// The operation to perform.
+    pub verb: KVTxnVerb,
+    /// The key, or prefix for tree operations, to operate on.
+    pub key: String,
+    /// The base64-encoded value to set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The flags to set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flags: Option<u64>,
+    /// The index used by check-and-set operations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<u64>,
+    /// The session used by lock operations.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+}
+
+/// A node operation within a transaction.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct NodeTxnOp {
+    /// The operation to perform.
+    pub verb: TxnVerb,
+    /// The node to operate on.
+    pub node: Node,
+}
+
+/// A service operation within a transaction.
+#[cfg(feature = "agent")]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ServiceTxnOp {
+    /// The operation to perform.
+    pub verb: TxnVerb,
+    /// The name of the node hosting the service.
+    pub node: String,
+    /// The service to operate on.
+    pub service: AgentService,
+}
+
+/// A check operation within a transaction.
+#[cfg(feature = "health")]
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CheckTxnOp {
+    /// The operation to perform.
+    pub verb: TxnVerb,
+    /// The check to operate on.
+    pub check: HealthCheck,
+}
+
+/// A single operation within a transaction.
+///
+/// KV operations are most easily created using the associated constructors,
+/// such as [TxnOp::kv_set] and [TxnOp::kv_check_index].
+#[derive(Clone, Debug, Serialize)]
+pub enum TxnOp {
+    #[serde(rename = "KV")]
+    KV(KVTxnOp),
+    Node(NodeTxnOp),
+    #[cfg(feature = "agent")]
+    Service(ServiceTxnOp),
+    #[cfg(feature = "health")]
+    Check(CheckTxnOp),
+}
+
+impl TxnOp {
+    /// This method creates a KV operation with the given verb and key.
+    fn kv(verb: KVTxnVerb, key: &str) -> KVTxnOp {
+        KVTxnOp { verb, key: key.to_owned(), value: None, flags: None, index: None, session: None }
+    }
+
+    /// This method creates an operation setting the key to the given value.
+    pub fn kv_set<V: AsRef<[u8]>>(key: &str, value: V) -> Self {
+        TxnOp::KV(KVTxnOp { value: Some(base64::encode(value)), ..Self::kv(KVTxnVerb::Set, key) })
+    }
+
+    /// This method creates an operation setting the key to the given value, if
+    /// its modify index matches `index`.
+    pub fn kv_cas<V: AsRef<[u8]>>(key: &str, value: V, index: u64) -> Self {
+        TxnOp::KV(KVTxnOp {
+            value: Some(base64::encode(value)),
+            index: Some(index),
+            ..Self::kv(KVTxnVerb::Cas, key)
+        })
+    }
+
+    /// This method creates an operation locking the key with the given
+    /// session, and setting it to the given value.
+    pub fn kv_lock<V: AsRef<[u8]>>(key: &str, value: V, session: &str) -> Self {
+        TxnOp::KV(KVTxnOp {
+            value: Some(base64::encode(value)),
+            session: Some(session.to_owned()),
+            ..Self::kv(KVTxnVerb::Lock, key)
+        })
+    }
+
+    /// This method creates an operation unlocking the key held by the given
+    /// session, and setting it to the given value.
+    pub fn kv_unlock<V: AsRef<[u8]>>(key: &str, value: V, session: &str) -> Self {
+        TxnOp::KV(KVTxnOp {
+            value: Some(base64::encode(value)),
+            session: Some(session.to_owned()),
+            ..Self::kv(KVTxnVerb::Unlock, key)
+        })
+    }
+
+    /// This method creates an operation getting the key.
+    pub fn kv_get(key: &str) -> Self {
+        TxnOp::KV(Self::kv(KVTxnVerb::Get, key))
+    }
+
+    /// This method creates an operation getting all keys with the given
+    /// prefix.
+    pub fn kv_get_tree(prefix: &str) -> Self {
+        TxnOp::KV(Self::kv(KVTxnVerb::GetTree, prefix))
+    }
+
+    /// This method creates an operation checking that the modify index of the
+    /// key matches `index`.
+    pub fn kv_check_index(key: &str, index: u64) -> Self {
+        TxnOp::KV(KVTxnOp { index: Some(index), ..Self::kv(KVTxnVerb::CheckIndex, key) })
+    }
+
+    /// This method creates an operation checking that the key is locked by the
+    /// given session.
+    pub fn kv_check_session(key: &str, session: &str) -> Self {
+        TxnOp::KV(KVTxnOp {
+            session: Some(session.to_owned()),
+            ..Self::kv(KVTxnVerb::CheckSession, key)
+        })
+    }
+
+    /// This method creates an operation checking that the key does not exist.
+    pub fn kv_check_not_exists(key: &str) -> Self {
+        TxnOp::KV(Self::kv(KVTxnVerb::CheckNotExists, key))
+    }
+
+    /// This method creates an operation deleting the key.
+    pub fn kv_delete(key: &str) -> Self {
+        TxnOp::KV(Self::kv(KVTxnVerb::Delete, key))
+    }
+
+    /// This method creates an operation deleting all keys with the given
+    /// prefix.
+    pub fn kv_delete_tree(prefix: &str) -> Self {
+        TxnOp::KV(Self::kv(KVTxnVerb::DeleteTree, prefix))
+    }
+
+    /// This method creates an operation deleting the key, if its modify index
+    /// matches `index`.
+    pub fn kv_delete_cas(key: &str, index: u64) -> Self {
+        TxnOp::KV(KVTxnOp { index: Some(index), ..Self::kv(KVTxnVerb::DeleteCas, key) })
+    }
+
+    /// This method creates an operation on the given node.
+    pub fn node(verb: TxnVerb, node: Node) -> Self {
+        TxnOp::Node(NodeTxnOp { verb, node })
+    }
+
+    /// This method creates an operation on the given service, hosted on the
+    /// given node.
+    #[cfg(feature = "agent")]
+    pub fn service(verb: TxnVerb, node: &str, service: AgentService) -> Self {
+        TxnOp::Service(ServiceTxnOp { verb, node: node.to_owned(), service })
+    }
+
+    /// This method creates an operation on the given check.
+    #[cfg(feature = "health")]
+    pub fn check(verb: TxnVerb, check: HealthCheck) -> Self {
+        TxnOp::Check(CheckTxnOp { verb, check })
+    }
+}
+
+/// The result of a single operation within a committed transaction.
+#[derive(Clone, Debug, Deserialize)]
+pub enum TxnResult {
+    #[serde(rename = "KV")]
+    KV(KVPair),
+    Node(Node),
+    #[cfg(feature = "agent")]
+    Service(AgentService),
+    #[cfg(feature = "health")]
+    Check(HealthCheck),
+}
+
+/// An error caused by a single operation within a transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TxnError {
+    /// The index of the operation which failed.
+    pub op_index: usize,
+    /// A description of the error.
+    pub what: String,
+}
+
+/// Response payload for the [Txn::execute_txn] method.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+pub struct TxnResponse {
+    /// The results of the operations, in order. Operations which do not
+    /// produce a result are omitted.
+    #[serde(deserialize_with = "deserialize_null_default")]
+    pub results: Vec<TxnResult>,
+    /// The errors which caused the transaction to be rolled back.
+    #[serde(deserialize_with = "deserialize_null_default")]
+    pub errors: Vec<TxnError>,
+}
+
+impl TxnResponse {
+    /// This method returns `true` if the transaction was committed.
+    pub fn is_committed(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// This trait provides methods for executing transactions against the
+/// `/txn` endpoint.
+#[async_trait]
+pub trait Txn: Sealed {
+    /// This method executes the given operations atomically. At most
+    /// [MAX_TXN_OPS] operations may be given.
+    ///
+    /// If any operation fails, the transaction is rolled back and the returned
+    /// [TxnResponse] contains the errors of the failed operations.
+    ///
+    /// For more information, consult the relevant endpoint's [API
+    /// documentation].
+    ///
+    /// [API documentation]: https://www.consul.io/api-docs/txn#create-transaction
+    async fn execute_txn(
+        &self,
+        ops: Vec<TxnOp>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<TxnResponse>;
+}
+
+#[async_trait]
+impl Txn for Client {
+    #[tracing::instrument]
+    async fn execute_txn(
+        &self,
+        ops: Vec<TxnOp>,
+        options: Option<QueryOptions>,
+    ) -> ConsulResult<TxnResponse> {
+        if ops.len() > MAX_TXN_OPS {
+            return Err(ConsulError::TxnTooLarge(ops.len()));
+        }
+        let endpoint = String::from("/v1/txn");
+        let response = self.execute(Method::PUT, &endpoint, None, Some(ops), options).await?;
+        // rolled back transactions return a conflict with the errors
+        match response.status() {
+            StatusCode::OK | StatusCode::CONFLICT => Ok(response.json().await?),
+            _ => Err(RequestError::from_response(Method::PUT, endpoint, response).await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{TxnOp, TxnResponse, TxnResult};
+    use crate::{Client, Config, ConsulError, Txn};
+
+    #[test]
+    fn test_serialize_ops() {
+        let ops = vec![
+            TxnOp::kv_set("a", "value"),
+            TxnOp::kv_check_not_exists("b"),
+            TxnOp::kv_delete_cas("c", 4),
+        ];
+        assert_eq!(
+            serde_json::to_value(ops).unwrap(),
+            json!([
+                { "KV": { "Verb": "set", "Key": "a", "Value": "dmFsdWU=" } },
+                { "KV": { "Verb": "check-not-exists", "Key": "b" } },
+                { "KV": { "Verb": "delete-cas", "Key": "c", "Index": 4 } },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_deserialize_response() {
+        let committed: TxnResponse = serde_json::from_value(json!({
+            "Results": [{ "KV": { "Key": "a", "Value": null, "ModifyIndex": 5 } }],
+            "Errors": null,
+        }))
+        .unwrap();
+        assert!(committed.is_committed());
+        assert!(matches!(&committed.results[0], TxnResult::KV(pair) if pair.key == "a"));
+
+        let rolled_back: TxnResponse = serde_json::from_value(json!({
+            "Results": null,
+            "Errors": [{ "OpIndex": 1, "What": "key \"b\" exists" }],
+        }))
+        .unwrap();
+        assert!(!rolled_back.is_committed());
+        assert_eq!(rolled_back.errors[0].op_index, 1);
+    }
+
+    #[tokio::test]
+    async fn test_txn_too_large() {
+        let client = Client::new(Config::new().unwrap());
+        let ops = (0..65).map(|i| TxnOp::kv_get(&i.to_string())).collect();
+        match client.execute_txn(ops, None).await {
+            Err(ConsulError::TxnTooLarge(65)) => {}
+            other => panic!("expected TxnTooLarge, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    future::Future,
+    io,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use hyper::{
+    client::connect::{Connected, Connection},
+    service::Service,
+    Body, Uri,
+};
+use reqwest::{Request, Response};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::UnixStream,
+};
+
+use crate::ConsulResult;
+
+/// The scheme of addresses of agents listening on a unix domain socket.
+pub(crate) const UNIX_SCHEME: &str = "unix://";
+
+/// The base URL of requests sent over a unix domain socket, which sets the
+/// `Host` header the agent sees.
+pub(crate) const UNIX_BASE_URL: &str = "http://localhost";
+
+/// An HTTP client sending requests to an agent over a unix domain socket.
+#[derive(Clone, Debug)]
+pub(crate) struct UnixSocket {
+    client: hyper::Client<UnixConnector, Body>,
+}
+
+impl UnixSocket {
+    /// This method creates a client for the socket of the given address, or
+    /// returns `None` if it is not a unix socket address.
+    pub(crate) fn from_address(address: &str) -> Option<UnixSocket> {
+        let path = address.strip_prefix(UNIX_SCHEME)?;
+        let connector = UnixConnector(Arc::from(Path::new(path)));
+        Some(UnixSocket { client: hyper::Client::builder().build(connector) })
+    }
+
+    /// This method sends a request built by the HTTP client over the socket.
+    pub(crate) async fn send(&self, request: Request) -> ConsulResult<Response> {
+        let request: hyper::Request<reqwest::Body> = request.try_into()?;
+        let (parts, body) = request.into_parts();
+        // request bodies are always buffered, as they are JSON or raw bytes
+        let body = body.as_bytes().map_or_else(Body::empty, |bytes| Body::from(bytes.to_vec()));
+        let response = self
+            .client
+            .request(hyper::Request::from_parts(parts, body))
+            .await
+            .map_err(io::Error::other)?;
+        Ok(response.into())
+    }
+}
+
+/// A connector opening a connection to the socket for each request.
+#[derive(Clone, Debug)]
+struct UnixConnector(Arc<Path>);
+
+impl Service<Uri> for UnixConnector {
+    type Response = UnixConnection;
+    type Error = io::Error;
+    type Future = Pin<Box<dyn Future<Output = io::Result<UnixConnection>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _: Uri) -> Self::Future {
+        let path = self.0.clone();
+        Box::pin(async move { UnixStream::connect(path).await.map(UnixConnection) })
+    }
+}
+
+/// A connection to the socket, implementing the traits required by Hyper.
+struct UnixConnection(UnixStream);
+
+impl Connection for UnixConnection {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+impl AsyncRead for UnixConnection {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixConnection {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use reqwest::{Method, StatusCode};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::UnixListener,
+        task::JoinHandle,
+    };
+
+    use crate::{Client, Config, ConsulError, RequestError};
+
+    /// This function answers a single request on the socket at the given path
+    /// with the given status line and JSON body, returning the request head.
+    fn serve_once(path: &Path, status: &'static str, body: &'static str) -> JoinHandle<String> {
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket() {
+        let path = std::env::temp_dir().join(format!("consul-oxide-{}.sock", std::process::id()));
+        let server = serve_once(&path, "200 OK", r#"["dc1"]"#);
+
+        let address = format!("unix://{}", path.display());
+        let client = Client::new(Config::builder().address(&address).build().unwrap());
+        let datacenters: Vec<String> = client.get("/v1/catalog/datacenters", None).await.unwrap();
+        assert_eq!(datacenters, ["dc1"]);
+        let request = server.await.unwrap();
+        assert!(request.starts_with("GET /v1/catalog/datacenters HTTP/1.1\r\n"), "{}", request);
+        assert!(request.to_lowercase().contains("host: localhost\r\n"), "{}", request);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unix_socket_error() {
+        let path =
+            std::env::temp_dir().join(format!("consul-oxide-{}-error.sock", std::process::id()));
+        let server = serve_once(&path, "403 Forbidden", "Permission denied");
+
+        let address = format!("unix://{}", path.display());
+        let client = Client::new(Config::builder().address(&address).build().unwrap());
+        let result = client.get::<_, Vec<String>>("/v1/catalog/datacenters", None).await;
+        server.await.unwrap();
+        match result {
+            Err(ConsulError::PermissionDenied(err)) => assert_eq!(
+                err,
+                RequestError {
+                    method: Method::GET,
+                    endpoint: String::from("/v1/catalog/datacenters"),
+                    status: StatusCode::FORBIDDEN,
+                    body: String::from("Permission denied"),
+                }
+            ),
+            other => panic!("expected permission denied, got {:?}", other),
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
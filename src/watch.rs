@@ -0,0 +1,268 @@
+#[cfg(feature = "catalog")]
+use std::collections::HashMap;
+use std::{future::Future, time::Duration};
+
+use futures::stream::{self, BoxStream, StreamExt};
+use rand::Rng;
+
+#[cfg(feature = "catalog")]
+use crate::Catalog;
+use crate::{sealed::Sealed, Client, ConsulResult, QueryMeta, QueryOptions};
+#[cfg(feature = "health")]
+use crate::{Health, ServiceEntry};
+#[cfg(feature = "kv")]
+use crate::{KVPair, KV};
+
+/// A stream of results produced by a watch.
+///
+/// The stream yields a new value whenever the watched data changes, and an
+/// error whenever a query fails. Errors do not terminate the stream: the
+/// watch backs off and retries, so callers may simply log them.
+pub type WatchStream<T> = BoxStream<'static, ConsulResult<T>>;
+
+/// Options to fine tune the behaviour of a watch.
+#[derive(Clone, Debug)]
+pub struct WatchOptions {
+    /// The query options used for each blocking query. The `wait_index` field
+    /// is managed by the watch, and is ignored.
+    pub query_options: QueryOptions,
+    /// The delay before retrying after the first failed query. The delay is
+    /// doubled after each consecutive failure. It is also the delay between
+    /// queries if the agent does not return an index, as they cannot block.
+    pub min_backoff: Duration,
+    /// The maximum delay before retrying after a failed query.
+    pub max_backoff: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            query_options: QueryOptions::default(),
+            min_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+/// This trait provides [futures::Stream]s which watch endpoints for changes
+/// using blocking queries.
+///
+/// For more information on blocking queries, see the [API documentation].
+///
+/// [API documentation]: https://www.consul.io/api-docs/features/blocking
+pub trait Watch: Sealed {
+    /// This method watches all keys sharing the given prefix, yielding the
+    /// entries every time they change.
+    ///
+    /// See [KV::list_entries] for more information.
+    #[cfg(feature = "kv")]
+    fn watch_entries(
+        &self,
+        prefix: &str,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<Vec<KVPair>>;
+
+    /// This method watches the instances of the given service, yielding them
+    /// every time they change.
+    ///
+    /// See [Health::list_service_instances] for more information.
+    #[cfg(feature = "health")]
+    fn watch_service_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<Vec<ServiceEntry>>;
+
+    /// This method watches the services registered in the datacenter,
+    /// yielding them every time they change.
+    ///
+    /// See [Catalog::list_datacenter_services] for more information.
+    #[cfg(feature = "catalog")]
+    fn watch_datacenter_services(
+        &self,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<HashMap<String, Vec<String>>>;
+}
+
+impl Watch for Client {
+    #[cfg(feature = "kv")]
+    fn watch_entries(
+        &self,
+        prefix: &str,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<Vec<KVPair>> {
+        let client = self.clone();
+        let prefix = prefix.to_owned();
+        watch(options.unwrap_or_default(), move |q| {
+            let client = client.clone();
+            let prefix = prefix.clone();
+            async move { client.list_entries_with_meta(&prefix, Some(q)).await }
+        })
+    }
+
+    #[cfg(feature = "health")]
+    fn watch_service_instances(
+        &self,
+        service: &str,
+        tags: &[&str],
+        passing_only: bool,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<Vec<ServiceEntry>> {
+        let client = self.clone();
+        let service = service.to_owned();
+        let tags: Vec<String> = tags.iter().map(|tag| (*tag).to_owned()).collect();
+        watch(options.unwrap_or_default(), move |q| {
+            let client = client.clone();
+            let service = service.clone();
+            let tags = tags.clone();
+            async move {
+                let tags: Vec<&str> = tags.iter().map(String::as_str).collect();
+                client
+                    .list_service_instances_with_meta(&service, &tags, passing_only, Some(q))
+                    .await
+            }
+        })
+    }
+
+    #[cfg(feature = "catalog")]
+    fn watch_datacenter_services(
+        &self,
+        options: Option<WatchOptions>,
+    ) -> WatchStream<HashMap<String, Vec<String>>> {
+        let client = self.clone();
+        watch(options.unwrap_or_default(), move |q| {
+            let client = client.clone();
+            async move { client.list_datacenter_services_with_meta(Some(q)).await }
+        })
+    }
+}
+
+/// Internal state of a watch stream.
+struct WatchState<T, F> {
+    fetch: F,
+    options: WatchOptions,
+    index: u64,
+    last: Option<T>,
+    failures: u32,
+    /// Whether the endpoint omitted its index, in which case queries cannot
+    /// block and are spaced out by the minimum backoff instead.
+    polling: bool,
+}
+
+/// This function creates a stream which repeatedly calls `fetch` as a blocking
+/// query, yielding its result whenever it changes.
+pub(crate) fn watch<T, F, Fut>(options: WatchOptions, fetch: F) -> WatchStream<T>
+where
+    T: Clone + PartialEq + Send + 'static,
+    F: FnMut(QueryOptions) -> Fut + Send + 'static,
+    Fut: Future<Output = ConsulResult<(T, QueryMeta)>> + Send + 'static,
+{
+    let state = WatchState { fetch, options, index: 0, last: None, failures: 0, polling: false };
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if state.failures > 0 {
+                let delay = backoff(&state.options, state.failures);
+                tokio::time::sleep(delay).await;
+            } else if state.polling {
+                tokio::time::sleep(state.options.min_backoff).await;
+            }
+            let mut query = state.options.query_options.clone();
+            query.wait_index = if state.index > 0 { Some(state.index) } else { None };
+            match (state.fetch)(query).await {
+                Ok((value, meta)) => {
+                    state.failures = 0;
+                    state.polling = meta.last_index.is_none();
+                    if let Some(index) = meta.last_index {
+                        state.index = next_index(state.index, index);
+                    }
+                    if state.last.as_ref() == Some(&value) {
+                        continue;
+                    }
+                    state.last = Some(value.clone());
+                    return Some((Ok(value), state));
+                }
+                Err(err) => {
+                    state.failures = state.failures.saturating_add(1);
+                    return Some((Err(err), state));
+                }
+            }
+        }
+    })
+    .boxed()
+}
+
+/// This function computes the index to use for the next blocking query.
+///
+/// If the index went backwards, e.g. because the data was deleted or the
+/// servers' state was restored from a snapshot, the index is reset so that
+/// the next query returns immediately. Otherwise, the index is clamped to be
+/// at least 1, as blocking on index 0 returns immediately.
+pub(crate) fn next_index(previous: u64, current: u64) -> u64 {
+    if current < previous {
+        0
+    } else {
+        current.max(1)
+    }
+}
+
+/// This function computes the jittered exponential delay to wait before
+/// retrying after the given number of consecutive failures.
+fn backoff(options: &WatchOptions, failures: u32) -> Duration {
+    let exp = options.min_backoff.saturating_mul(1 << failures.saturating_sub(1).min(16));
+    let delay = exp.min(options.max_backoff);
+    // pick a random delay between half and the full delay
+    delay / 2 + delay.mul_f64(rand::thread_rng().gen_range(0.0..0.5))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+
+    use super::{backoff, next_index, watch, WatchOptions};
+    use crate::QueryMeta;
+
+    #[test]
+    fn test_next_index() {
+        assert_eq!(next_index(0, 10), 10);
+        assert_eq!(next_index(10, 10), 10);
+        assert_eq!(next_index(10, 12), 12);
+        // index went backwards, reset
+        assert_eq!(next_index(12, 5), 0);
+        // never block on index 0
+        assert_eq!(next_index(0, 0), 1);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let options = WatchOptions {
+            min_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(10),
+            ..Default::default()
+        };
+        for failures in 1..40 {
+            let delay = backoff(&options, failures);
+            let cap = Duration::from_secs(1 << (failures - 1).min(16)).min(options.max_backoff);
+            assert!(delay >= cap / 2 && delay <= cap);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_without_index() {
+        let options = WatchOptions { min_backoff: Duration::from_millis(50), ..Default::default() };
+        let mut calls = 0;
+        let stream = watch(options, move |q| {
+            assert_eq!(q.wait_index, None);
+            calls += 1;
+            async move { Ok((calls, QueryMeta::default())) }
+        });
+        // queries which cannot block are spaced out instead of looping
+        let start = Instant::now();
+        let values: Vec<u32> = stream.take(3).map(Result::unwrap).collect().await;
+        assert_eq!(values, [1, 2, 3]);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
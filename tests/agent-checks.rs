@@ -2,7 +2,7 @@ use consul_oxide::{AgentChecks, Client, Config, RegisterCheckPayload};
 
 #[tokio::test]
 async fn test_register_check() {
-    let client = Client::new(Config::default());
+    let client = Client::new(Config::new().unwrap());
     let check = RegisterCheckPayload {
         name: "test_check".to_string(),
         interval: "10m".to_string(),
@@ -10,10 +10,10 @@ async fn test_register_check() {
         http: Some("http://example.com".to_string()),
         ..Default::default()
     };
-    client.register_check(check).await.expect("failed to register check");
+    client.register_check(check, None).await.expect("failed to register check");
 
-    let checks = client.list_checks().await.unwrap();
+    let checks = client.list_checks(None).await.unwrap();
     assert!(checks.contains_key("test_check"));
 
-    client.deregister_check("test_check").await.expect("failed to deregister check");
+    client.deregister_check("test_check", None).await.expect("failed to deregister check");
 }
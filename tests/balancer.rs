@@ -0,0 +1,18 @@
+use consul_oxide::{BalancerOptions, BalancingStrategy, Client, Config, LoadBalancer};
+
+#[tokio::test]
+async fn test_load_balancer() {
+    let client = Client::new(Config::new().unwrap());
+    let options = BalancerOptions {
+        service: String::from("consul"),
+        strategy: BalancingStrategy::RoundRobin,
+        ..Default::default()
+    };
+    let balancer = LoadBalancer::start(&client, options).await.unwrap();
+    let instances = balancer.instances();
+    assert!(!instances.is_empty());
+
+    let address = balancer.next_address().unwrap();
+    assert_eq!(address.port, instances[0].service.port);
+    assert!(balancer.next_instance().is_some());
+}
@@ -0,0 +1,42 @@
+use consul_oxide::{
+    AgentService, Catalog, CatalogDeregistrationPayload, CatalogRegistrationPayload, Client, Config,
+};
+
+#[tokio::test]
+async fn test_catalog_register() {
+    let client = Client::new(Config::new().unwrap());
+    let payload = CatalogRegistrationPayload {
+        node: String::from("catalog-test"),
+        address: String::from("10.0.0.1"),
+        service: Some(AgentService {
+            id: String::from("catalog-test-web"),
+            service: String::from("catalog-test-web"),
+            tags: Some(vec![String::from("v1")]),
+            port: 8080,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    client.register(payload, None).await.unwrap();
+
+    let node = client.get_node("catalog-test", None).await.unwrap().unwrap();
+    assert_eq!(node.node.unwrap().address, "10.0.0.1");
+    assert!(node.services.contains_key("catalog-test-web"));
+
+    let services = client.list_node_services("catalog-test", None).await.unwrap().unwrap();
+    assert_eq!(services.services.len(), 1);
+
+    let nodes = client.list_service_nodes("catalog-test-web", &["v1"], None).await.unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].service_port, 8080);
+    let nodes = client.list_service_nodes("catalog-test-web", &["v2"], None).await.unwrap();
+    assert!(nodes.is_empty());
+
+    let nodes = client.list_datacenter_nodes(None).await.unwrap();
+    assert!(nodes.iter().any(|node| node.node == "catalog-test"));
+
+    let payload =
+        CatalogDeregistrationPayload { node: String::from("catalog-test"), ..Default::default() };
+    client.deregister(payload, None).await.unwrap();
+    assert!(client.get_node("catalog-test", None).await.unwrap().is_none());
+}
@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use consul_oxide::{Client, Config, ElectionOptions, LeaderElection, KV};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn test_leader_election() {
+    let client = Client::new(Config::new().unwrap());
+    let election = LeaderElection::new(
+        &client,
+        ElectionOptions {
+            key: String::from("test_leader_election/leader"),
+            identity: String::from("worker-1"),
+            wait_time: Duration::from_secs(5),
+            ..Default::default()
+        },
+    );
+
+    let mut leaders = election.observe();
+    assert_eq!(leaders.next().await.unwrap().unwrap(), None);
+
+    let leadership = election.campaign().await.unwrap();
+    assert_eq!(leaders.next().await.unwrap().unwrap().as_deref(), Some("worker-1"));
+    assert_eq!(election.leader().await.unwrap().as_deref(), Some("worker-1"));
+    assert!(!leadership.is_lost());
+
+    leadership.resign().await.unwrap();
+    assert_eq!(leaders.next().await.unwrap().unwrap(), None);
+
+    client.delete_entries("test_leader_election/", None).await.unwrap();
+}
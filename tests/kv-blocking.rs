@@ -0,0 +1,34 @@
+use std::time::{Duration, Instant};
+
+use consul_oxide::{Client, Config, KVPair, QueryOptions, KV};
+
+#[tokio::test]
+async fn test_kv_blocking_query() {
+    let client = Client::new(Config::new().unwrap());
+    let pair = KVPair {
+        key: String::from("test_kv_blocking_query/testkey"),
+        value: b"testvalue".to_vec(),
+        ..Default::default()
+    };
+    assert!(client.put_entry(&pair, None).await.unwrap());
+
+    let (entries, meta) =
+        client.get_entry_with_meta("test_kv_blocking_query/testkey", None).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    let index = meta.last_index.expect("missing X-Consul-Index");
+    assert!(meta.known_leader);
+
+    // nothing changes, so the query should block until the wait time elapses
+    let start = Instant::now();
+    let options = QueryOptions {
+        wait_index: Some(index),
+        wait_time: Some(Duration::from_secs(1)),
+        ..Default::default()
+    };
+    let (_, meta) =
+        client.list_entries_with_meta("test_kv_blocking_query/", Some(options)).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(1));
+    assert_eq!(meta.last_index, Some(index));
+
+    client.delete_entry("test_kv_blocking_query/testkey", None).await.unwrap();
+}
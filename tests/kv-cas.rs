@@ -0,0 +1,30 @@
+use consul_oxide::{Client, Config, KVPair, KV};
+
+#[tokio::test]
+async fn test_kv_cas() {
+    let client = Client::new(Config::new().unwrap());
+    // binary values are stored verbatim
+    let mut pair = KVPair {
+        key: String::from("test_kv_cas/testkey"),
+        value: vec![0, 159, 146, 150],
+        ..Default::default()
+    };
+    // an index of 0 only creates the key if it does not exist
+    assert!(client.cas_entry(&pair, 0, None).await.unwrap());
+    assert!(!client.cas_entry(&pair, 0, None).await.unwrap());
+
+    let entry = client.get_entry("test_kv_cas/testkey", None).await.unwrap().remove(0);
+    assert_eq!(entry.value, pair.value);
+    let index = entry.modifyindex.unwrap();
+
+    pair.value = b"updated".to_vec();
+    assert!(!client.cas_entry(&pair, index + 1, None).await.unwrap());
+    assert!(client.cas_entry(&pair, index, None).await.unwrap());
+
+    // the index has moved on, so the stale delete must fail
+    assert!(!client.cas_delete_entry("test_kv_cas/testkey", index, None).await.unwrap());
+    let index =
+        client.get_entry("test_kv_cas/testkey", None).await.unwrap()[0].modifyindex.unwrap();
+    assert!(client.cas_delete_entry("test_kv_cas/testkey", index, None).await.unwrap());
+    assert!(client.get_entry("test_kv_cas/testkey", None).await.unwrap().is_empty());
+}
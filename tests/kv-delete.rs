@@ -0,0 +1,7 @@
+use consul_oxide::{Client, Config, ConsulError, KV};
+
+#[tokio::test]
+async fn test_kv_delete_entries_empty_prefix() {
+    let client = Client::new(Config::new().unwrap());
+    assert!(matches!(client.delete_entries("", None).await, Err(ConsulError::EmptyKey)));
+}
@@ -0,0 +1,38 @@
+use consul_oxide::{
+    Client, Config, ImportOptions, KVExport, KVExportEntry, KVImportChange, KVPair, KV,
+};
+
+#[tokio::test]
+async fn test_kv_export_import() {
+    let client = Client::new(Config::new().unwrap());
+    let pair = KVPair {
+        key: String::from("test_kv_export/a"),
+        value: b"first".to_vec(),
+        flags: Some(7),
+        ..Default::default()
+    };
+    assert!(client.put_entry(&pair, None).await.unwrap());
+
+    let mut entries = client.export_entries("test_kv_export/", None).await.unwrap();
+    assert_eq!(entries, vec![KVExportEntry { key: pair.key.clone(), flags: 7, value: pair.value }]);
+
+    entries[0].value = b"second".to_vec();
+    entries.push(KVExportEntry { key: String::from("test_kv_export/b"), ..Default::default() });
+    let options = ImportOptions { dry_run: true, ..Default::default() };
+    let changes = client.import_entries(&entries, options).await.unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            KVImportChange::Updated(String::from("test_kv_export/a")),
+            KVImportChange::Created(String::from("test_kv_export/b")),
+        ]
+    );
+    // nothing is written in a dry run
+    assert_eq!(client.get_entry_raw("test_kv_export/a", None).await.unwrap().unwrap(), b"first");
+
+    let options = ImportOptions { transactional: true, ..Default::default() };
+    client.import_entries(&entries, options).await.unwrap();
+    assert_eq!(client.export_entries("test_kv_export/", None).await.unwrap(), entries);
+
+    client.delete_entries("test_kv_export/", None).await.unwrap();
+}
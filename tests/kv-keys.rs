@@ -0,0 +1,29 @@
+use consul_oxide::{Client, Config, KVPair, KV};
+
+#[tokio::test]
+async fn test_kv_keys_and_raw() {
+    let client = Client::new(Config::new().unwrap());
+    for key in ["test_kv_keys/a", "test_kv_keys/b/c", "test_kv_keys/b/d"] {
+        let pair =
+            KVPair { key: key.to_owned(), value: key.as_bytes().to_vec(), ..Default::default() };
+        assert!(client.put_entry(&pair, None).await.unwrap());
+    }
+
+    let keys = client.list_keys("test_kv_keys/", None, None).await.unwrap();
+    assert_eq!(keys, vec!["test_kv_keys/a", "test_kv_keys/b/c", "test_kv_keys/b/d"]);
+    let keys = client.list_keys("test_kv_keys/", Some("/"), None).await.unwrap();
+    assert_eq!(keys, vec!["test_kv_keys/a", "test_kv_keys/b/"]);
+    assert!(client.list_keys("test_kv_keys/missing/", None, None).await.unwrap().is_empty());
+
+    let raw = client.get_entry_raw("test_kv_keys/b/c", None).await.unwrap();
+    assert_eq!(raw.as_deref(), Some(&b"test_kv_keys/b/c"[..]));
+    assert_eq!(client.get_entry_raw("test_kv_keys/missing", None).await.unwrap(), None);
+
+    assert!(client.delete_entries("test_kv_keys/b/", None).await.unwrap());
+    assert_eq!(
+        client.list_keys("test_kv_keys/", None, None).await.unwrap(),
+        vec!["test_kv_keys/a"]
+    );
+    assert!(client.delete_entries("test_kv_keys/", None).await.unwrap());
+    assert!(client.list_keys("test_kv_keys/", None, None).await.unwrap().is_empty());
+}
@@ -0,0 +1,18 @@
+use std::collections::HashMap;
+
+use consul_oxide::{Client, Config, Json, TypedKV, KV};
+
+#[tokio::test]
+async fn test_kv_typed() {
+    let client = Client::new(Config::new().unwrap());
+    let mut value = HashMap::new();
+    value.insert(String::from("replicas"), 3);
+    assert!(client.put_typed::<Json, _>("test_kv_typed/config", &value, None).await.unwrap());
+    let read: Option<HashMap<String, u32>> =
+        client.get_typed::<Json, _>("test_kv_typed/config", None).await.unwrap();
+    assert_eq!(read, Some(value));
+    let missing: Option<HashMap<String, u32>> =
+        client.get_typed::<Json, _>("test_kv_typed/missing", None).await.unwrap();
+    assert_eq!(missing, None);
+    client.delete_entry("test_kv_typed/config", None).await.unwrap();
+}
@@ -6,28 +6,35 @@ use consul_oxide::{Client, Config, KVPair, KV};
 
 #[tokio::test]
 async fn test_kv_methods() {
-    let config = Config::default();
+    let config = Config::new().unwrap();
     let client = Client::new(config);
-    let r = client.list_entries("", None).await.unwrap();
+    let r = client.list_entries("test_kv_methods/", None).await.unwrap();
     assert!(r.is_empty());
 
     let pair = KVPair {
-        key: String::from("testkey"),
-        value: String::from("testvalue"),
+        key: String::from("test_kv_methods/testkey"),
+        value: b"testvalue".to_vec(),
+        flags: Some(42),
         ..Default::default()
     };
 
     assert!(client.put_entry(&pair, None).await.unwrap());
 
-    let b64val = client.get_entry("testkey", None).await.unwrap().into_iter().next().unwrap().value;
-    let bytes = base64::decode(b64val).unwrap();
-    assert_eq!(std::str::from_utf8(&bytes).unwrap(), "\"testvalue\"");
-
-    let r = client.list_entries("t", None).await.unwrap();
+    let entry = client
+        .get_entry("test_kv_methods/testkey", None)
+        .await
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap();
+    assert_eq!(entry.value, b"testvalue");
+    assert_eq!(entry.flags, Some(42));
+
+    let r = client.list_entries("test_kv_methods/t", None).await.unwrap();
     assert!(!r.is_empty());
 
-    client.delete_entry("testkey", None).await.unwrap();
+    client.delete_entry("test_kv_methods/testkey", None).await.unwrap();
 
-    let r = client.list_entries("", None).await.unwrap();
+    let r = client.list_entries("test_kv_methods/", None).await.unwrap();
     assert!(r.is_empty());
 }
@@ -0,0 +1,26 @@
+use std::collections::HashMap;
+
+use consul_oxide::{Client, Config, KVPair, LayeredConfig, KV};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn test_layered_config() {
+    let client = Client::new(Config::new().unwrap());
+    for (key, value) in [("layers/global/replicas", "1"), ("layers/web/replicas", "3")] {
+        let pair =
+            KVPair { key: key.to_owned(), value: value.as_bytes().to_vec(), ..Default::default() };
+        assert!(client.put_entry(&pair, None).await.unwrap());
+    }
+
+    let layers = vec![String::from("layers/global"), String::from("layers/web")];
+    let config = LayeredConfig::new(&client, layers, None);
+    let loaded: HashMap<String, u32> = config.load_as().await.unwrap();
+    assert_eq!(loaded["replicas"], 3);
+
+    let mut stream = config.watch_as::<HashMap<String, u32>>();
+    assert_eq!(stream.next().await.unwrap().unwrap()["replicas"], 3);
+    client.delete_entry("layers/web/replicas", None).await.unwrap();
+    assert_eq!(stream.next().await.unwrap().unwrap()["replicas"], 1);
+
+    client.delete_entries("layers/", None).await.unwrap();
+}
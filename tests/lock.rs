@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use consul_oxide::{Client, Config, Lock, LockOptions, Session, KV};
+
+#[tokio::test]
+async fn test_lock_exclusive() {
+    let client = Client::new(Config::new().unwrap());
+    let options = LockOptions {
+        key: String::from("test_lock_exclusive/lock"),
+        wait_time: Duration::from_secs(1),
+        try_once: true,
+        ..Default::default()
+    };
+
+    let lock = Lock::acquire(&client, options.clone()).await.unwrap().expect("lock not acquired");
+    assert!(!lock.is_lost());
+    // the lock is held, so a second attempt should time out
+    assert!(Lock::acquire(&client, options.clone()).await.unwrap().is_none());
+
+    lock.unlock().await.unwrap();
+    let lock = Lock::acquire(&client, options).await.unwrap().expect("lock not acquired");
+    lock.unlock().await.unwrap();
+
+    client.delete_entries("test_lock_exclusive/", None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_lock_lost() {
+    let client = Client::new(Config::new().unwrap());
+    let options = LockOptions { key: String::from("test_lock_lost/lock"), ..Default::default() };
+
+    let lock = Lock::acquire(&client, options).await.unwrap().expect("lock not acquired");
+    // invalidating the session releases the lock
+    client.destroy_session(lock.session(), None).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(5), lock.lost()).await.expect("lock not lost");
+    assert!(lock.is_lost());
+
+    lock.unlock().await.unwrap();
+    client.delete_entries("test_lock_lost/", None).await.unwrap();
+}
@@ -0,0 +1,49 @@
+use std::time::Duration;
+
+use consul_oxide::{Client, Config, KVPair, Mirror, MirrorEvent, MirrorOptions, KV};
+
+#[tokio::test]
+async fn test_mirror_pull_and_push() {
+    let client = Client::new(Config::new().unwrap());
+    let pair = KVPair {
+        key: String::from("mirror/app/config"),
+        value: b"remote".to_vec(),
+        ..Default::default()
+    };
+    assert!(client.put_entry(&pair, None).await.unwrap());
+
+    let directory = std::env::temp_dir().join(format!("consul-mirror-test-{}", std::process::id()));
+    let options = MirrorOptions {
+        prefix: String::from("mirror"),
+        directory: directory.clone(),
+        push: true,
+        scan_interval: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let mut mirror = Mirror::start(&client, options).await.unwrap();
+    let event = mirror.next_event().await.unwrap().unwrap();
+    assert_eq!(event, MirrorEvent::Pulled(pair.key.clone()));
+    let path = directory.join("app").join("config");
+    assert_eq!(std::fs::read(&path).unwrap(), b"remote");
+
+    std::fs::write(&path, b"local").unwrap();
+    let event = mirror.next_event().await.unwrap().unwrap();
+    assert_eq!(event, MirrorEvent::Pushed(pair.key.clone()));
+    assert_eq!(client.get_entry_raw(&pair.key, None).await.unwrap().unwrap(), b"local");
+
+    // our own write is not pulled back, so a second edit is pushed as well
+    std::fs::write(&path, b"edited").unwrap();
+    let event = mirror.next_event().await.unwrap().unwrap();
+    assert_eq!(event, MirrorEvent::Pushed(pair.key.clone()));
+    assert_eq!(client.get_entry_raw(&pair.key, None).await.unwrap().unwrap(), b"edited");
+
+    let pair = KVPair { value: b"updated".to_vec(), ..pair };
+    assert!(client.put_entry(&pair, None).await.unwrap());
+    let event = mirror.next_event().await.unwrap().unwrap();
+    assert_eq!(event, MirrorEvent::Pulled(pair.key.clone()));
+    assert_eq!(std::fs::read(&path).unwrap(), b"updated");
+
+    drop(mirror);
+    client.delete_entries("mirror/", None).await.unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+}
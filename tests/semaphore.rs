@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use consul_oxide::{Client, Config, ConsulError, Semaphore, SemaphoreOptions, KV};
+
+#[tokio::test]
+async fn test_semaphore_limit() {
+    let client = Client::new(Config::new().unwrap());
+    let options = SemaphoreOptions {
+        prefix: String::from("test_semaphore_limit"),
+        limit: 2,
+        wait_time: Duration::from_secs(1),
+        try_once: true,
+        ..Default::default()
+    };
+
+    let first = Semaphore::acquire(&client, options.clone()).await.unwrap().expect("not acquired");
+    let second = Semaphore::acquire(&client, options.clone()).await.unwrap().expect("not acquired");
+    // both slots are held, so a third attempt should time out
+    assert!(Semaphore::acquire(&client, options.clone()).await.unwrap().is_none());
+
+    // contenders must agree on the limit
+    let mismatched = SemaphoreOptions { limit: 3, ..options.clone() };
+    match Semaphore::acquire(&client, mismatched).await {
+        Err(ConsulError::SemaphoreLimitConflict { limit: 3, existing: 2 }) => {}
+        other => panic!("expected limit conflict, got {:?}", other),
+    }
+
+    first.release().await.unwrap();
+    let third = Semaphore::acquire(&client, options).await.unwrap().expect("not acquired");
+    assert!(!second.is_lost());
+
+    second.release().await.unwrap();
+    third.release().await.unwrap();
+
+    client.delete_entries("test_semaphore_limit/", None).await.unwrap();
+}
@@ -64,7 +64,7 @@ async fn test_session_info() {
 
     assert_eq!(session_entries.len(), 1);
 
-    let session_entry = session_entries.get(0);
+    let session_entry = session_entries.first();
 
     assert_eq!(*session_entry.as_ref().unwrap().name.as_ref().unwrap(), unique_test_identifier);
 
@@ -157,7 +157,7 @@ async fn test_renew_session() {
 }
 
 async fn set_up() -> (Client, String) {
-    let config = Config::default();
+    let config = Config::new().unwrap();
     let client = Client::new(config);
 
     let unique_test_identifier: String =
@@ -0,0 +1,32 @@
+use consul_oxide::{Client, Config, Txn, TxnOp, TxnResult, KV};
+
+#[tokio::test]
+async fn test_txn_commit_and_rollback() {
+    let client = Client::new(Config::new().unwrap());
+    let ops = vec![
+        TxnOp::kv_set("txn/a", "first"),
+        TxnOp::kv_set("txn/b", "second"),
+        TxnOp::kv_get_tree("txn/"),
+    ];
+    let response = client.execute_txn(ops, None).await.unwrap();
+    assert!(response.is_committed());
+    let keys: Vec<_> = response
+        .results
+        .iter()
+        .filter_map(|result| match result {
+            TxnResult::KV(pair) => Some(pair.key.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert!(keys.contains(&"txn/a") && keys.contains(&"txn/b"));
+
+    // the failed check rolls back the delete
+    let ops = vec![TxnOp::kv_delete("txn/a"), TxnOp::kv_check_not_exists("txn/b")];
+    let response = client.execute_txn(ops, None).await.unwrap();
+    assert!(!response.is_committed());
+    assert_eq!(response.errors[0].op_index, 1);
+    assert_eq!(client.get_entry("txn/a", None).await.unwrap().len(), 1);
+
+    let response = client.execute_txn(vec![TxnOp::kv_delete_tree("txn/")], None).await.unwrap();
+    assert!(response.is_committed());
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use consul_oxide::{Client, Config, KVPair, QueryOptions, Watch, WatchOptions, KV};
+use futures::StreamExt;
+
+#[tokio::test]
+async fn test_watch_entries() {
+    let client = Client::new(Config::new().unwrap());
+    let mut pair = KVPair {
+        key: String::from("watch/testkey"),
+        value: b"first".to_vec(),
+        ..Default::default()
+    };
+    assert!(client.put_entry(&pair, None).await.unwrap());
+
+    let options = WatchOptions {
+        query_options: QueryOptions {
+            wait_time: Some(Duration::from_secs(5)),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut stream = client.watch_entries("watch/", Some(options));
+    let entries = stream.next().await.unwrap().unwrap();
+    assert_eq!(entries.len(), 1);
+
+    pair.value = b"second".to_vec();
+    assert!(client.put_entry(&pair, None).await.unwrap());
+    let changed = stream.next().await.unwrap().unwrap();
+    assert_eq!(changed.len(), 1);
+    assert_ne!(changed[0].value, entries[0].value);
+
+    client.delete_entry("watch/testkey", None).await.unwrap();
+    let deleted = stream.next().await.unwrap().unwrap();
+    assert!(deleted.is_empty());
+}